@@ -1,28 +1,73 @@
-use super::util::{InputMode, Lifetime, Prefix, ResponseFormat};
-use base64;
+use super::history;
+use super::info::Info;
+use super::progress::{self, Meter};
+use super::throttle;
+use super::util::{self, InputMode, Lifetime, Prefix, Progress, Range, ResponseFormat, Size, Wait};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::{fmt::Display, io, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, Read},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 use ureq::{self, Request, Response};
 
+#[allow(clippy::enum_variant_names)]
 pub enum ErrorKind {
     UReqError(String),
+    /// The server turned down the password or api key, with its message
+    Denied(String),
     ServerError(&'static str),
     LocalIoError(io::Error),
+    Timeout(String),
+    NoSuchName(String),
+    Corrupt(String),
+    /// The file was turned down before it was sent
+    Rejected(String),
+    /// With the size of the file, when the server gave it
+    RangeNotSatisfiable(Option<u64>),
 }
 
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ErrorKind::UReqError(msg) => write!(f, "{}", msg),
+            ErrorKind::Denied(msg) => write!(f, "{}", msg),
             ErrorKind::ServerError(msg) => write!(f, "{}", msg),
             ErrorKind::LocalIoError(err) => write!(f, "local io error: {}", err),
+            ErrorKind::Timeout(id) => write!(f, "timed out waiting for {}", id),
+            ErrorKind::NoSuchName(prefix) => {
+                write!(f, "no file named {}", prefix.trim_end_matches('|'))
+            }
+            ErrorKind::Corrupt(msg) => write!(f, "corrupt split file: {}", msg),
+            ErrorKind::Rejected(msg) => write!(f, "{}", msg),
+            ErrorKind::RangeNotSatisfiable(_) => {
+                write!(f, "the range starts past the end of the file")
+            }
         }
     }
 }
 
 impl From<ureq::Error> for ErrorKind {
     fn from(err: ureq::Error) -> Self {
+        if let ureq::Error::Status(416, resp) = &err {
+            // Content-Range: bytes */N
+            let size = resp
+                .header("content-range")
+                .and_then(|range| range.strip_prefix("bytes */"))
+                .and_then(|size| size.trim().parse().ok());
+            return ErrorKind::RangeNotSatisfiable(size);
+        }
+        if let ureq::Error::Status(401 | 403, _) = &err {
+            return ErrorKind::Denied(
+                err.into_response()
+                    .and_then(|resp| resp.into_string().ok())
+                    .unwrap_or("malformed response body".into()),
+            );
+        }
         ErrorKind::UReqError(match err {
             ureq::Error::Status(_, resp) => resp
                 .into_string()
@@ -34,12 +79,32 @@ impl From<ureq::Error> for ErrorKind {
     }
 }
 
+/// Passwords are sent as a header so they don't end up in proxy or server logs
+pub const PASSWORD_HEADER: &str = "X-Scratch-Password";
+/// The password a file should have after an update
+pub const NEW_PASSWORD_HEADER: &str = "X-Scratch-New-Password";
+/// Which file the server sent, when it picked one for latest
+pub const ID_HEADER: &str = "X-Scratch-Id";
+/// The id that asks the server for the newest file
+const DEFAULT_ID: &str = "latest";
+
+#[derive(Clone, Copy)]
 pub struct ClientOpts<'a> {
     response_format: &'a Option<ResponseFormat>,
+    progress: Progress,
+    limit_rate: Option<Size>,
 }
 impl<'a> ClientOpts<'a> {
-    pub fn new(response_format: &'a Option<ResponseFormat>) -> ClientOpts<'a> {
-        Self { response_format }
+    pub fn new(
+        response_format: &'a Option<ResponseFormat>,
+        progress: Progress,
+        limit_rate: Option<Size>,
+    ) -> ClientOpts<'a> {
+        Self {
+            response_format,
+            progress,
+            limit_rate,
+        }
     }
 }
 
@@ -53,6 +118,8 @@ pub struct PushArgs {
     pw: Option<String>,
     prefix: Option<Prefix>,
     lifetime: Option<Lifetime>,
+    file_name: Option<String>,
+    content_type: Option<String>,
 }
 
 pub struct PullArgs<W>
@@ -64,6 +131,8 @@ where
 
     api_key: Option<String>,
     pw: Option<String>,
+    wait: Option<Wait>,
+    range: Option<Range>,
 
     output: W,
 }
@@ -79,6 +148,23 @@ pub struct DeleteArgs {
     id: String,
 }
 
+pub struct InfoArgs {
+    api_key: String,
+    endpoint: String,
+    id: String,
+}
+
+pub struct UpdateArgs {
+    api_key: String,
+    endpoint: String,
+    id: String,
+    current_pw: Option<String>,
+
+    lifetime: Option<Lifetime>,
+    private: Option<bool>,
+    pw: Option<String>,
+}
+
 pub struct StatsArgs {
     api_key: String,
     endpoint: String,
@@ -95,6 +181,7 @@ pub struct BootstrapResponse {
 }
 
 impl PushArgs {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         api_key: String,
         endpoint: String,
@@ -114,8 +201,17 @@ impl PushArgs {
             pw,
             prefix,
             lifetime,
+            file_name: None,
+            content_type: None,
         }
     }
+
+    /// Name and type the raw url serves the file with
+    pub fn file(mut self, file_name: Option<String>, content_type: Option<String>) -> Self {
+        self.file_name = file_name;
+        self.content_type = content_type;
+        self
+    }
 }
 
 impl<W> PullArgs<W>
@@ -127,6 +223,7 @@ where
         id: Option<String>,
         api_key: Option<String>,
         pw: Option<String>,
+        wait: Option<Wait>,
         output: W,
     ) -> Self {
         Self {
@@ -134,9 +231,17 @@ where
             id,
             api_key,
             pw,
+            wait,
+            range: None,
             output,
         }
     }
+
+    /// Only pull part of the file
+    pub fn range(mut self, range: Option<Range>) -> Self {
+        self.range = range;
+        self
+    }
 }
 
 impl ListArgs {
@@ -155,6 +260,38 @@ impl DeleteArgs {
     }
 }
 
+impl InfoArgs {
+    pub fn new(api_key: String, endpoint: String, id: String) -> Self {
+        Self {
+            api_key,
+            endpoint,
+            id,
+        }
+    }
+}
+
+impl UpdateArgs {
+    pub fn new(
+        api_key: String,
+        endpoint: String,
+        id: String,
+        current_pw: Option<String>,
+        lifetime: Option<Lifetime>,
+        private: Option<bool>,
+        pw: Option<String>,
+    ) -> Self {
+        Self {
+            api_key,
+            endpoint,
+            id,
+            current_pw,
+            lifetime,
+            private,
+            pw,
+        }
+    }
+}
+
 impl StatsArgs {
     pub fn new(api_key: String, endpoint: String) -> Self {
         Self { api_key, endpoint }
@@ -184,30 +321,115 @@ fn request(method: &'static str, endpoint: &str, opts: &ClientOpts, action: &str
 fn get_content_type(resp: &Response) -> Option<ResponseFormat> {
     let hval = resp.header("content-type");
     match hval {
-        Some(header) => match ResponseFormat::from_str(header) {
-            Ok(fmt) => Some(fmt),
-            Err(_) => None,
-        },
+        Some(header) => ResponseFormat::from_str(header).ok(),
         None => None,
     }
 }
 
-fn extract_id(text: &String, content_type: ResponseFormat) -> Option<String> {
+fn extract_id(text: &str, content_type: ResponseFormat) -> Option<String> {
     match content_type {
         ResponseFormat::TextJavascript => {
             const ID_PATTERN: &str = r#"^\{\s*"id"\s*:\s*"(.*)"\s*\}$"#;
             lazy_static! {
                 static ref ID_RE: Regex = Regex::new(ID_PATTERN).unwrap();
             }
-            match ID_RE.captures(&text) {
-                Some(captures) => Some(captures[1].into()),
-                None => None,
-            }
+            ID_RE.captures(text).map(|captures| captures[1].into())
         }
         ResponseFormat::TextPlain => Some(text.trim().into()),
     }
 }
 
+fn extract_ids(text: &str) -> Vec<String> {
+    const IDS_PATTERN: &str = r#""id"\s*:\s*"([^"]*)""#;
+    lazy_static! {
+        static ref IDS_RE: Regex = Regex::new(IDS_PATTERN).unwrap();
+    }
+    IDS_RE
+        .captures_iter(text)
+        .map(|captures| captures[1].into())
+        .collect()
+}
+
+/// An inline Content-Disposition, so browsers still show what they can.
+/// Names that aren't plain ascii are also given in RFC 5987 form, after a
+/// fallback with those characters replaced.
+fn content_disposition(file_name: &str) -> String {
+    let plain = |c: char| c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\';
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if plain(c) { c } else { '_' })
+        .collect();
+    if file_name.chars().all(plain) {
+        return format!("inline; filename=\"{}\"", fallback);
+    }
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!(
+        "inline; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
+
+/// The file name in a Content-Disposition header, preferring the RFC 5987
+/// form
+fn disposition_file_name(resp: &Response) -> Option<String> {
+    file_name_param(resp.header("content-disposition")?)
+}
+/// The file name in a Content-Disposition header, preferring the RFC 5987
+/// filename* when it's there and decodes
+fn file_name_param(header: &str) -> Option<String> {
+    let param = |name: &str| {
+        header.split(';').find_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            match key.trim().eq_ignore_ascii_case(name) {
+                true => Some(value.trim().to_string()),
+                false => None,
+            }
+        })
+    };
+    let extended = param("filename*").and_then(|value| {
+        let (charset, rest) = value.split_once('\'')?;
+        let (_, encoded) = rest.split_once('\'')?;
+        let bytes = percent_decode(encoded)?;
+        match charset.eq_ignore_ascii_case("utf-8") {
+            true => String::from_utf8(bytes).ok(),
+            false => None,
+        }
+    });
+    extended
+        .filter(|name| !name.is_empty())
+        .or_else(|| param("filename").map(|value| value.trim_matches('"').to_string()))
+        .filter(|name| !name.is_empty())
+}
+
+fn percent_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        match b {
+            b'%' => {
+                let hex = tail
+                    .get(..2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    Some(bytes)
+}
+
 trait ResponseBodyHelpers {
     fn text_or_err(self) -> Result<String, ErrorKind>;
 }
@@ -223,7 +445,7 @@ impl ResponseBodyHelpers for Response {
 
 pub fn push<R>(args: PushArgs, opts: ClientOpts, report_id: R) -> Result<String, ErrorKind>
 where
-    R: FnOnce(&String) -> (),
+    R: FnOnce(&str, &str),
 {
     let created_id: String;
     let resp_text: String;
@@ -239,7 +461,7 @@ where
             create = create.query("private", &private.to_string());
         }
         if let Some(pw) = args.pw {
-            create = create.query("pw", &pw);
+            create = create.set(PASSWORD_HEADER, &pw);
         }
         if let Some(burn) = args.burn {
             create = create.query("burn", &burn.to_string());
@@ -265,10 +487,10 @@ where
         };
     }
 
-    report_id(&resp_text);
+    report_id(&created_id, &resp_text);
 
     {
-        let push = request(
+        let mut push = request(
             "POST",
             &args.endpoint,
             &opts,
@@ -276,46 +498,411 @@ where
         )
         .set("Authorization", &format!("Bearer {}", args.api_key))
         .set("Content-Length", &args.input.size().to_string());
+        if let Some(content_type) = &args.content_type {
+            push = push.set("Content-Type", content_type);
+        }
+        if let Some(file_name) = &args.file_name {
+            push = push.set("Content-Disposition", &content_disposition(file_name));
+        }
+        let mut meter = Meter::new(opts.progress, "push", &created_id, Some(args.input.size()));
         let resp = match args.input {
-            InputMode::Buffer(buf) => push.send_bytes(&buf),
-            InputMode::File(file) => push.send(file),
+            InputMode::Buffer(buf) => push.send(progress::Reader {
+                inner: throttle::Reader::new(io::Cursor::new(buf), opts.limit_rate),
+                meter: &mut meter,
+            }),
+            InputMode::File(file) => push.send(progress::Reader {
+                inner: throttle::Reader::new(file, opts.limit_rate),
+                meter: &mut meter,
+            }),
+            InputMode::Stream(reader, _) => push.send(progress::Reader {
+                inner: throttle::Reader::new(reader, opts.limit_rate),
+                meter: &mut meter,
+            }),
         };
+        meter.finish(resp.is_ok());
         match resp {
             Ok(resp) => Ok(resp.text_or_err()?),
             Err(err) => Err(err.into()),
         }
     }
 }
-pub fn pull<W>(mut args: PullArgs<W>, opts: ClientOpts) -> Result<String, ErrorKind>
+/// Pull a file into the output, and return its id, which is the newest
+/// match when a name or prefix was asked for, and the content type the
+/// server sent it with
+pub fn pull<W>(
+    mut args: PullArgs<W>,
+    opts: ClientOpts,
+) -> Result<(String, Option<String>), ErrorKind>
 where
     W: io::Write,
 {
-    const DEFAULT_ID: &str = "latest";
-    let mut pull = request(
-        "GET",
-        &args.endpoint,
-        &opts,
-        &format!("file/{}", args.id.unwrap_or(DEFAULT_ID.to_string())),
-    );
-    if let Some(api_key) = args.api_key {
-        pull = pull.set("Authorization", &format!("Bearer {}", api_key));
+    let id = args.id.take().unwrap_or(DEFAULT_ID.to_string());
+    let (resp, id) = match args.wait.take() {
+        Some(wait) => wait_for_file(&args, &opts, &id, wait)?,
+        None if util::is_name_prefix(&id) => {
+            let found =
+                newest_with_prefix(&args, &id)?.ok_or_else(|| ErrorKind::NoSuchName(id.clone()))?;
+            (pull_request(&args, &opts, &found).call()?, found)
+        }
+        None => (pull_request(&args, &opts, &id).call()?, id),
+    };
+    // the server picks latest itself, and the response may say which file
+    // that was
+    let id = match id == DEFAULT_ID {
+        true => served_id(&resp).unwrap_or(id),
+        false => id,
+    };
+    let mut total = resp
+        .header("content-length")
+        .and_then(|length| length.parse().ok());
+    let partial = resp.status() == 206;
+    let content_type = resp.header("content-type").map(String::from);
+    if partial && content_range_start(&resp) != args.range.map(|range| range.start) {
+        return Err(ErrorKind::ServerError(
+            "malformed resp from server: wrong range",
+        ));
     }
-    if let Some(pw) = args.pw {
-        pull = pull.query("pw", &pw);
+    let mut body: Box<dyn io::Read> = Box::new(resp.into_reader());
+    if let (Some(range), false) = (args.range, partial) {
+        // the server sent the whole file, so cut the range out of it here
+        io::copy(&mut (&mut body).take(range.start), &mut io::sink())
+            .map_err(ErrorKind::LocalIoError)?;
+        total = total.map(|total: u64| total.saturating_sub(range.start));
+        if let Some(count) = range.count() {
+            body = Box::new(body.take(count));
+            total = total.map(|total| total.min(count));
+        }
     }
-    let resp = pull.call()?;
-    match io::copy(&mut resp.into_reader(), &mut args.output) {
+    let mut meter = Meter::new(opts.progress, "pull", &id, total);
+    let mut output = progress::Writer {
+        inner: throttle::Writer::new(&mut args.output, opts.limit_rate),
+        meter: &mut meter,
+    };
+    let copied = io::copy(&mut body, &mut output);
+    meter.finish(copied.is_ok());
+    match copied {
         Ok(_) => {}
         Err(err) => return Err(ErrorKind::LocalIoError(err)),
     };
-    Ok("".into())
+    Ok((id, content_type))
+}
+/// Start pulling a file without reading it, so its body can be handed to a
+/// push.  Returns the body and its length, when the server sent one.
+pub fn open(
+    mut args: PullArgs<io::Sink>,
+    opts: ClientOpts,
+) -> Result<(Box<dyn Read + Send + Sync>, Option<u64>), ErrorKind> {
+    let id = args.id.take().unwrap_or(DEFAULT_ID.into());
+    let id = match util::is_name_prefix(&id) {
+        true => newest_with_prefix(&args, &id)?.ok_or_else(|| ErrorKind::NoSuchName(id.clone()))?,
+        false => id,
+    };
+    let resp = pull_request(&args, &opts, &id).call()?;
+    let size = resp
+        .header("content-length")
+        .and_then(|length| length.parse().ok());
+    Ok((resp.into_reader(), size))
+}
+/// The size and content type of a stored file, as the server reports them
+/// to a HEAD request
+pub fn stored(
+    endpoint: &str,
+    api_key: &str,
+    id: &str,
+    pw: Option<&str>,
+    opts: ClientOpts,
+) -> Result<(Option<u64>, Option<String>), ErrorKind> {
+    let mut head = request("HEAD", endpoint, &opts, &format!("file/{}", id))
+        .set("Authorization", &format!("Bearer {}", api_key));
+    if let Some(pw) = pw {
+        head = head.set(PASSWORD_HEADER, pw);
+    }
+    let resp = head.call()?;
+    let size = resp
+        .header("content-length")
+        .and_then(|length| length.parse().ok());
+    Ok((size, resp.header("content-type").map(String::from)))
+}
+/// What the server says about the file a pull would get, without pulling it
+pub struct Head {
+    /// The id, with a name resolved to the newest file pushed with it
+    pub id: String,
+    pub file_name: Option<String>,
+    pub size: Option<u64>,
+    pub content_type: Option<String>,
+}
+
+pub fn remote_head<W>(args: &PullArgs<W>, opts: ClientOpts) -> Result<Head, ErrorKind>
+where
+    W: io::Write,
+{
+    let id = args.id.clone().unwrap_or(DEFAULT_ID.into());
+    let id = match util::is_name_prefix(&id) {
+        true => newest_with_prefix(args, &id)?.ok_or_else(|| ErrorKind::NoSuchName(id.clone()))?,
+        false => id,
+    };
+    let resp = file_request("HEAD", args, &opts, &id).call()?;
+    let id = match id == DEFAULT_ID {
+        true => served_id(&resp).unwrap_or(id),
+        false => id,
+    };
+    Ok(Head {
+        file_name: disposition_file_name(&resp),
+        size: resp
+            .header("content-length")
+            .and_then(|length| length.parse().ok()),
+        content_type: resp.header("content-type").map(String::from),
+        id,
+    })
+}
+fn pull_request<W>(args: &PullArgs<W>, opts: &ClientOpts, id: &str) -> Request
+where
+    W: io::Write,
+{
+    file_request("GET", args, opts, id)
+}
+fn file_request<W>(method: &'static str, args: &PullArgs<W>, opts: &ClientOpts, id: &str) -> Request
+where
+    W: io::Write,
+{
+    let mut pull = request(method, &args.endpoint, opts, &format!("file/{}", id));
+    if let Some(api_key) = &args.api_key {
+        pull = pull.set("Authorization", &format!("Bearer {}", api_key));
+    }
+    if let Some(pw) = &args.pw {
+        pull = pull.set(PASSWORD_HEADER, pw);
+    }
+    if let Some(range) = &args.range {
+        pull = pull.set("Range", &range.header());
+    }
+    pull
+}
+/// The id of the file a response is for, from the id header, or the url
+/// the request was redirected to
+fn served_id(resp: &Response) -> Option<String> {
+    let redirected = || {
+        let path = resp.get_url().split(['?', '#']).next()?;
+        let (_, id) = path.rsplit_once("/file/")?;
+        percent_decode(id).and_then(|id| String::from_utf8(id).ok())
+    };
+    resp.header(ID_HEADER)
+        .map(|id| id.trim().to_string())
+        .or_else(redirected)
+        .filter(|id| !id.is_empty() && id != DEFAULT_ID)
+}
+/// Where the body of a 206 response starts, from Content-Range: bytes A-B/N
+fn content_range_start(resp: &Response) -> Option<u64> {
+    let range = resp.header("content-range")?.strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+/// The newest file whose id starts with prefix, by when it was pushed: the
+/// "created" time in the file list where the server sends one, else the
+/// time this machine recorded in its history.  The list's order is only
+/// relied on when no match has a known time.
+fn newest_with_prefix<W>(args: &PullArgs<W>, prefix: &str) -> Result<Option<String>, ErrorKind>
+where
+    W: io::Write,
+{
+    let api_key = match &args.api_key {
+        Some(api_key) => api_key.clone(),
+        None => {
+            return Err(ErrorKind::ServerError(
+                "finding a file by prefix requires an api key",
+            ))
+        }
+    };
+    let list = ListArgs::new(api_key, args.endpoint.clone());
+    let entries = extract_entries(&list_text(&list)?);
+    let pushed = history::list().unwrap_or_default();
+    let candidates: Vec<(String, Option<u64>)> = entries
+        .into_iter()
+        .filter_map(|mut fields| {
+            let id = fields.remove("id")?;
+            if !id.starts_with(prefix) {
+                return None;
+            }
+            let created = fields
+                .get("created")
+                .and_then(|created| created.parse().ok())
+                .or_else(|| {
+                    pushed
+                        .iter()
+                        .find(|entry| entry.id == id)
+                        .map(|entry| entry.pushed)
+                });
+            Some((id, created))
+        })
+        .collect();
+    // max_by_key keeps the last of equal times, so ties go to list order
+    let newest = match candidates.iter().any(|(_, created)| created.is_some()) {
+        true => candidates
+            .into_iter()
+            .filter(|(_, created)| created.is_some())
+            .max_by_key(|(_, created)| *created),
+        false => candidates.into_iter().last(),
+    };
+    Ok(newest.map(|(id, _)| id))
+}
+/// Poll until the file exists, backing off between attempts.  A prefix
+/// resolves to the newest file pushed with it, whose id is returned with
+/// the response.
+fn wait_for_file<W>(
+    args: &PullArgs<W>,
+    opts: &ClientOpts,
+    id: &str,
+    wait: Wait,
+) -> Result<(Response, String), ErrorKind>
+where
+    W: io::Write,
+{
+    const MIN_DELAY: Duration = Duration::from_millis(250);
+    const MAX_DELAY: Duration = Duration::from_secs(10);
+    // a timeout too far off to represent waits forever
+    let deadline = match wait {
+        Wait::Forever => None,
+        Wait::Timeout(timeout) => Instant::now().checked_add(timeout),
+    };
+    let mut delay = MIN_DELAY;
+    loop {
+        let found = match util::is_wait_prefix(id) {
+            true => newest_with_prefix(args, id)?,
+            false => Some(id.to_string()),
+        };
+        if let Some(found) = found {
+            match pull_request(args, opts, &found).call() {
+                Ok(resp) => return Ok((resp, found)),
+                Err(ureq::Error::Status(404, _)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ErrorKind::Timeout(id.into()));
+            }
+            delay = delay.min(remaining);
+        }
+        thread::sleep(delay);
+        delay = (delay * 2).min(MAX_DELAY);
+    }
 }
 pub fn list(args: ListArgs, opts: ClientOpts) -> Result<String, ErrorKind> {
     let list = request("GET", &args.endpoint, &opts, "file")
         .set("Authorization", &format!("Bearer {}", args.api_key));
     let resp = list.call()?;
-    Ok(resp.text_or_err()?)
+    resp.text_or_err()
+}
+pub fn list_ids(args: ListArgs) -> Result<Vec<String>, ErrorKind> {
+    Ok(extract_ids(&list_text(&args)?))
+}
+/// The file list as json, whatever format the user asked for
+fn list_text(args: &ListArgs) -> Result<String, ErrorKind> {
+    let format = Some(ResponseFormat::TextJavascript);
+    let opts = ClientOpts::new(&format, Progress::Never, None);
+    let list = request("GET", &args.endpoint, &opts, "file")
+        .set("Authorization", &format!("Bearer {}", args.api_key));
+    list.call()?.text_or_err()
 }
+/// Find out what the server will say about a file without pulling it.  HEAD
+/// requests tell whether it exists, its size, and whether it needs a
+/// password or the api key, and the file list may know more.  This assumes
+/// the server doesn't count a HEAD request as a download of a burn file.
+pub fn info(args: InfoArgs, opts: ClientOpts) -> Result<Info, ErrorKind> {
+    let head = |api_key: Option<&str>| {
+        let mut head = request("HEAD", &args.endpoint, &opts, &format!("file/{}", args.id));
+        if let Some(api_key) = api_key {
+            head = head.set("Authorization", &format!("Bearer {}", api_key));
+        }
+        head
+    };
+    let mut info = Info {
+        id: args.id.clone(),
+        ..Info::default()
+    };
+    match head(Some(&args.api_key)).call() {
+        Ok(resp) => {
+            info.exists = true;
+            info.password = Some(false);
+            info.size = resp
+                .header("content-length")
+                .and_then(|length| length.parse().ok());
+            info.file_name = disposition_file_name(&resp);
+            info.content_type = resp.header("content-type").map(String::from);
+        }
+        Err(ureq::Error::Status(404, _)) => return Ok(info),
+        Err(ureq::Error::Status(401 | 403, _)) => {
+            // the file turned the api key away, which only says something
+            // about its password if the server takes the key otherwise
+            let stats = request("GET", &args.endpoint, &opts, "me/stats")
+                .set("Authorization", &format!("Bearer {}", args.api_key));
+            match stats.call() {
+                Ok(_) => {
+                    info.exists = true;
+                    info.password = Some(true);
+                }
+                Err(ureq::Error::Status(401 | 403, _)) => {
+                    return Err(ErrorKind::ServerError("the server rejected the api key"))
+                }
+                Err(_) => info.exists = true,
+            }
+        }
+        Err(err) => return Err(err.into()),
+    }
+    if info.password == Some(false) {
+        // with no password in the way, only a private file turns away a
+        // request without the api key
+        info.private = match head(None).call() {
+            Ok(_) => Some(false),
+            Err(ureq::Error::Status(401 | 403, _)) => Some(true),
+            Err(_) => None,
+        };
+    }
+    let list = ListArgs::new(args.api_key, args.endpoint);
+    if let Ok(text) = list_text(&list) {
+        if let Some(fields) = extract_entry(&text, &info.id) {
+            let number = |name: &str| fields.get(name).and_then(|value| value.parse().ok());
+            let flag = |name: &str| fields.get(name).and_then(|value| value.parse().ok());
+            info.size = info.size.or(number("size"));
+            info.expires = number("expires");
+            info.burn = flag("burn");
+            info.private = info.private.or(flag("private"));
+        }
+    }
+    Ok(info)
+}
+
+/// The fields of one file in the file list, with strings unquoted
+fn extract_entry(text: &str, id: &str) -> Option<HashMap<String, String>> {
+    extract_entries(text)
+        .into_iter()
+        .find(|fields| fields.get("id").map(String::as_str) == Some(id))
+}
+
+/// The fields of every file in the file list, in the order they're listed
+fn extract_entries(text: &str) -> Vec<HashMap<String, String>> {
+    const ENTRY_PATTERN: &str = r#"\{[^{}]*\}"#;
+    const FIELD_PATTERN: &str = r#""(\w+)"\s*:\s*(?:"([^"]*)"|([^,}\s]+))"#;
+    lazy_static! {
+        static ref ENTRY_RE: Regex = Regex::new(ENTRY_PATTERN).unwrap();
+        static ref FIELD_RE: Regex = Regex::new(FIELD_PATTERN).unwrap();
+    }
+    ENTRY_RE
+        .find_iter(text)
+        .map(|entry| {
+            FIELD_RE
+                .captures_iter(entry.as_str())
+                .map(|field| {
+                    let value = field.get(2).or_else(|| field.get(3));
+                    (
+                        field[1].to_string(),
+                        value.map_or("", |v| v.as_str()).into(),
+                    )
+                })
+                .collect::<HashMap<_, _>>()
+        })
+        .collect()
+}
+
 pub fn delete(args: DeleteArgs, opts: ClientOpts) -> Result<String, ErrorKind> {
     let delete = request(
         "DELETE",
@@ -325,13 +912,37 @@ pub fn delete(args: DeleteArgs, opts: ClientOpts) -> Result<String, ErrorKind> {
     )
     .set("Authorization", &format!("Bearer {}", args.api_key));
     let resp = delete.call()?;
-    Ok(resp.text_or_err()?)
+    resp.text_or_err()
+}
+/// Change a file's attributes in place.  Returns false if the server can't,
+/// in which case the file is untouched.
+pub fn update(args: UpdateArgs, opts: ClientOpts) -> Result<bool, ErrorKind> {
+    let mut update = request("PATCH", &args.endpoint, &opts, &format!("file/{}", args.id))
+        .set("Authorization", &format!("Bearer {}", args.api_key))
+        .set("Content-Length", "0");
+    if let Some(pw) = args.current_pw {
+        update = update.set(PASSWORD_HEADER, &pw);
+    }
+    if let Some(lifetime) = args.lifetime {
+        update = update.query("lifetime", &lifetime.0);
+    }
+    if let Some(private) = args.private {
+        update = update.query("private", &private.to_string());
+    }
+    if let Some(pw) = args.pw {
+        update = update.set(NEW_PASSWORD_HEADER, &pw);
+    }
+    match update.call() {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(405 | 501, _)) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
 }
 pub fn stats(args: StatsArgs, opts: ClientOpts) -> Result<String, ErrorKind> {
     let stats = request("GET", &args.endpoint, &opts, "me/stats")
         .set("Authorization", &format!("Bearer {}", args.api_key));
     let resp = stats.call()?;
-    Ok(resp.text_or_err()?)
+    resp.text_or_err()
 }
 pub fn bootstrap(args: BootstrapArgs) -> Result<BootstrapResponse, ErrorKind> {
     let authorization = format!(
@@ -356,3 +967,95 @@ pub fn bootstrap(args: BootstrapArgs) -> Result<BootstrapResponse, ErrorKind> {
         dataplane_endpoint: req!("dataplane_endpoint"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_names_are_sent_as_is() {
+        assert_eq!(
+            content_disposition("report.pdf"),
+            "inline; filename=\"report.pdf\""
+        );
+        assert_eq!(
+            file_name_param(&content_disposition("report.pdf")).unwrap(),
+            "report.pdf"
+        );
+    }
+
+    #[test]
+    fn other_names_round_trip_through_filename_star() {
+        for name in [
+            "résumé.txt",
+            "say \"hi\".txt",
+            "back\\slash",
+            "tab\there",
+            "日本.md",
+        ] {
+            let header = content_disposition(name);
+            assert!(header.contains("filename*=UTF-8''"), "{}", header);
+            // the fallback stays a valid quoted string
+            let fallback = header.split(';').nth(1).unwrap();
+            assert!(
+                fallback.is_ascii() && fallback.matches('"').count() == 2,
+                "{}",
+                header
+            );
+            assert_eq!(file_name_param(&header).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn odd_filename_stars_fall_back_to_filename() {
+        let cases = [
+            "attachment; filename=\"plain.txt\"; filename*=",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8''",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8''bad%2",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8''bad%zz",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8''bad%+1",
+            "attachment; filename=\"plain.txt\"; filename*=UTF-8''%ff%fe",
+            "attachment; filename=\"plain.txt\"; filename*=ISO-8859-1''caf%E9",
+        ];
+        for header in cases {
+            assert_eq!(
+                file_name_param(header).as_deref(),
+                Some("plain.txt"),
+                "{}",
+                header
+            );
+        }
+        assert_eq!(file_name_param("inline"), None);
+        assert_eq!(file_name_param("inline; filename=\"\""), None);
+        assert_eq!(
+            file_name_param("inline; FILENAME*=utf-8'en'a%20b").unwrap(),
+            "a b"
+        );
+    }
+
+    #[test]
+    fn latest_is_named_by_the_id_header() {
+        let resp = |headers: &str| {
+            format!("HTTP/1.1 200 OK\r\n{}\r\n", headers)
+                .parse::<Response>()
+                .unwrap()
+        };
+        assert_eq!(
+            served_id(&resp("X-Scratch-Id: 0000003d\r\n")).unwrap(),
+            "0000003d"
+        );
+        assert_eq!(served_id(&resp("X-Scratch-Id: latest\r\n")), None);
+        assert_eq!(served_id(&resp("X-Scratch-Id: \r\n")), None);
+        assert_eq!(served_id(&resp("")), None);
+    }
+
+    #[test]
+    fn percent_decoding_needs_two_hex_digits() {
+        assert_eq!(percent_decode("a%20b%2F").unwrap(), b"a b/");
+        assert_eq!(percent_decode("").unwrap(), b"");
+        for text in ["%", "%2", "%g0", "%+1", "%-1", "50%"] {
+            assert_eq!(percent_decode(text), None, "{}", text);
+        }
+    }
+}
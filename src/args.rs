@@ -1,125 +1,29 @@
-use std::{error::Error as StdError, fmt::Display, io, str::FromStr};
+use std::{
+    collections::BTreeMap, error::Error as StdError, fmt::Display, io, path::PathBuf, str::FromStr,
+};
 
+use super::completions;
 use super::config_file as cf;
+use super::emit;
+use super::help;
+use super::history;
+use super::hooks;
+use super::secrets;
+use super::share::ShareToken;
+use super::spec;
 use super::util;
-use lexopt;
-use toml;
-
-const HELP: &str = "
-USAGE: scratch [OPTIONS] [COMMAND]
-
-Easily transmit small bits of short-lived data.
-
-OPTIONS:
-    --api-key API_KEY       API Key found in your account settings page.
-    --endpoint ENDPOINT     Endpoint for dataplane operations, found in
-                            your account settings page.
-    --out-format FORMAT     Control how responses are rendered.  Allowed
-                            values [text/plain, text/javascript, txt, js]
-
-COMMAND:
-    push        Upload the contents of a file
-    pull        Get the contents of a file
-    ls          List all file metadata
-    rm          Remove a file by id
-    stats       Get usage stats for your account
-    bootstrap   Create a valid config file
-";
-
-const PUSH_HELP: &str = r#"
-USAGE: scratch push [OPTIONS] FILE
-
-Upload a file.  The key of the created file is printed.  When pushing from
-stdin, buffers the entire input into memory.
-(see scratch --help for global options)
-
-OPTIONS:
-    --stdin                 (default) Push data from stdin
-                            Note: buffers input to memory before writing
-    --file FILE             Push the named file
-    --lifetime LIFETIME     How long the file should live eg. 10m
-                            Format: \d+(h|m|s)
-    --private               Whether the file can be read by anyone.
-    --pw PASSWORD           Optional password that will be required to
-                            read the file.  Format: [a-zA-Z0-9._-]{1,20}
-    --burn                  Whether the file should be deleted the first
-                            time it's read
-    --prefix PREFIX         Optional prefix for the random file key.
-                            Useful for segmenting temporary files by use.
-                            Format: [a-zA-Z0-9._-:|]{1,64}
-    --url                   Prints out the complete file url, not just the id.
-                            Useful when pushing public data to access without
-                            a scratch client.
-
-EXAMPLES:
-    scratch push --lifetime 2h < ~/.ssh/id_rsa.pub
-    scratch push --burn --prefix creds.aws: --file ~/.aws/config
-    scratch push --no-private --url <<< "hello, world"
-"#;
-
-const PULL_HELP: &str = r#"
-USAGE: scratch pull [OPTIONS] [ID]
-
-Pull a file by id.  If the file was pushed with a password, it is
-required to pull the file.  When ID is omitted, pulls the most recently
-pushed file.
-(see scratch --help for global options)
-
-ARGUMENTS:
-    ID          The id of the file to pull.  If you pushed the file with a
-                prefix, you must include that prefix.  Defaults to the
-                id of the most recently pushed file.
-
-OPTIONS:
-    --anon      pull without passing credentials.  only public files
-                (pushed with private=false) can be pulled anonymously.
-    --pw PW     password the file was pushed with, if any.
-
-"#;
-
-const LIST_HELP: &str = r#"
-USAGE: scratch ls
-
-List file ids and their metadata.
-(see scratch --help for global options)
-"#;
-
-const DELETE_HELP: &str = r#"
-USAGE: scratch rm ID
-
-Delete a file by id.
-
-ARGUMENTS:
-    ID  The id of the file to delete.  If you pushed the file with a prefix,
-        you must include that prefix.  Deletion does not require a password.
-
-EXAMPLES:
-    scratch delete c869d7cc
-    scratch delete creds.aws:f0022e5a
-"#;
-
-const STATS_HELP: &str = r#"
-USAGE: scratch stats
-
-List usage and capacity stats for your account.
-(see scratch --help for global options)
-"#;
-
-const BOOTSTRAP_HELP: &str = r#"
-USAGE: scratch bootstrap
-
-Creates a minimal valid config file to use the service.
-By default this writes to ~/.kilobytetools/config.
-
-OPTIONS:
-    --stdout    Write to stdout instead of the default path.
-"#;
 
 #[derive(Debug)]
 pub enum ErrorKind {
     Lexopt(lexopt::Error),
-    BadSubcommand(String),
+    BadSubcommand(String, Option<String>),
+    UnknownFlag(String, Option<String>),
+    MisplacedFlag(String, Option<&'static str>, Vec<&'static str>),
+    InvalidValue(String, String),
+    UnexpectedArgument(String, &'static str),
+    UnreadableInput(&'static str, PathBuf, io::Error),
     MalformedConfigFile(&'static str, toml::de::Error),
+    UnreadableConfigFile(&'static str, io::Error),
     MissingArgument(&'static str, &'static str),
     MissingPositionalArgument(&'static str),
     IoError(io::Error),
@@ -131,8 +35,43 @@ impl StdError for ErrorKind {}
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ErrorKind::BadSubcommand(name) => {
-                write!(f, "unknown subcommand {}", name)
+            ErrorKind::BadSubcommand(name, suggestion) => {
+                write!(f, "unknown subcommand {}", name)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            ErrorKind::UnknownFlag(flag, suggestion) => {
+                write!(f, "unknown option '{}'", flag)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            ErrorKind::MisplacedFlag(flag, command, owners) => match command {
+                Some(command) => write!(
+                    f,
+                    "option '{}' doesn't apply to '{}', it is only used by: {}",
+                    flag,
+                    command,
+                    owners.join(", ")
+                ),
+                None => write!(
+                    f,
+                    "option '{}' must come after the command it applies to: {}",
+                    flag,
+                    owners.join(", ")
+                ),
+            },
+            ErrorKind::InvalidValue(flag, msg) => {
+                write!(f, "invalid value for '{}': {}", flag, msg)
+            }
+            ErrorKind::UnexpectedArgument(value, command) => {
+                write!(f, "unexpected argument '{}' for '{}'", value, command)
+            }
+            ErrorKind::UnreadableInput(origin, path, err) => {
+                write!(f, "unable to read {} {}: {}", origin, path.display(), err)
             }
             ErrorKind::Lexopt(err) => {
                 write!(f, "{}", err)
@@ -140,6 +79,9 @@ impl Display for ErrorKind {
             ErrorKind::MalformedConfigFile(filename, msg) => {
                 write!(f, "malformed config file at {}: {}", filename, msg)
             }
+            ErrorKind::UnreadableConfigFile(filename, err) => {
+                write!(f, "unable to read config file at {}: {}", filename, err)
+            }
             ErrorKind::MissingArgument(cli_name, config_name) => {
                 write!(
                     f,
@@ -183,16 +125,29 @@ pub struct CommonOptions {
     pub endpoint: Option<String>,
 
     pub response_format: Option<util::ResponseFormat>,
+    pub progress: Option<util::Progress>,
+    /// Most bytes per second to push or pull
+    pub limit_rate: Option<util::Size>,
+    pub hooks: hooks::Hooks,
 }
 
 pub enum Command {
-    Help(&'static str),
+    Help(String),
     Push(PushArgs),
     Pull(PullArgs),
     List,
     Delete(DeleteArgs),
     Stats,
     Bootstrap(BootstrapArgs),
+    Completions(CompletionsArgs),
+    Qr(QrArgs),
+    Watch(WatchArgs),
+    History,
+    Info(InfoArgs),
+    Touch(ChangeArgs),
+    Chmod(ChangeArgs),
+    Copy(CopyArgs),
+    Move(CopyArgs),
 }
 
 enum CommandName {
@@ -202,6 +157,15 @@ enum CommandName {
     Delete,
     Stats,
     Bootstrap,
+    Completions,
+    Qr,
+    Watch,
+    History,
+    Info,
+    Touch,
+    Chmod,
+    Copy,
+    Move,
 }
 
 impl FromStr for CommandName {
@@ -215,11 +179,46 @@ impl FromStr for CommandName {
             "rm" => Ok(CommandName::Delete),
             "stats" => Ok(CommandName::Stats),
             "bootstrap" => Ok(CommandName::Bootstrap),
-            _ => Err(ErrorKind::BadSubcommand(s.into())),
+            "completions" => Ok(CommandName::Completions),
+            "qr" => Ok(CommandName::Qr),
+            "watch" => Ok(CommandName::Watch),
+            "history" => Ok(CommandName::History),
+            "info" => Ok(CommandName::Info),
+            "touch" => Ok(CommandName::Touch),
+            "chmod" => Ok(CommandName::Chmod),
+            "cp" => Ok(CommandName::Copy),
+            "mv" => Ok(CommandName::Move),
+            _ => Err(ErrorKind::BadSubcommand(
+                s.into(),
+                spec::suggest(s, spec::COMMANDS.iter().map(|c| c.name)),
+            )),
         }
     }
 }
 
+impl CommandName {
+    fn spec(&self) -> &'static spec::Command {
+        let name = match self {
+            CommandName::Push => "push",
+            CommandName::Pull => "pull",
+            CommandName::List => "ls",
+            CommandName::Delete => "rm",
+            CommandName::Stats => "stats",
+            CommandName::Bootstrap => "bootstrap",
+            CommandName::Completions => "completions",
+            CommandName::Qr => "qr",
+            CommandName::Watch => "watch",
+            CommandName::History => "history",
+            CommandName::Info => "info",
+            CommandName::Touch => "touch",
+            CommandName::Chmod => "chmod",
+            CommandName::Copy => "cp",
+            CommandName::Move => "mv",
+        };
+        spec::command(name).expect("every command is described in spec")
+    }
+}
+
 #[derive(Default)]
 pub struct PushArgs {
     pub lifetime: Option<util::Lifetime>,
@@ -227,8 +226,39 @@ pub struct PushArgs {
     pub pw: Option<String>,
     pub burn: Option<bool>,
     pub prefix: Option<util::Prefix>,
-    pub input: Option<util::InputMode>,
-    pub render_url: bool,
+    /// Every input to push, in order, with the same options
+    pub inputs: Vec<PushInput>,
+    pub atomic: Option<bool>,
+    /// Push the input in parts of this size, plus a manifest listing them
+    pub split: Option<util::Size>,
+    pub render_url: Option<bool>,
+    pub gen_pw: bool,
+    pub pw_token: Option<bool>,
+    pub share: Option<bool>,
+    pub encrypt: Option<bool>,
+    pub emit: Option<emit::Emit>,
+    /// Let --emit put the api key in the command, for private files
+    pub emit_with_api_key: bool,
+    pub qr: Option<bool>,
+    pub copy_id: Option<bool>,
+    /// Older files with this name are deleted after the push
+    pub name: Option<util::Name>,
+    /// Push files that aren't private even when they look like they contain secrets
+    pub allow_secrets: Option<bool>,
+    /// What files that aren't private are checked against before they're pushed
+    pub secrets: secrets::Scanner,
+    /// Served with the raw url instead of a type detected from the input
+    pub content_type: Option<util::ContentType>,
+}
+
+pub struct PushInput {
+    pub input: util::InputMode,
+    /// The path as given, or stdin or clipboard
+    pub label: String,
+    /// Name of the pushed file, used as the output path of --emit
+    pub file_name: Option<String>,
+    /// Where the input came from, for the history file
+    pub source: String,
 }
 
 #[derive(Default)]
@@ -236,6 +266,19 @@ pub struct PullArgs {
     pub id: Option<String>,
     pub anon: Option<bool>,
     pub pw: Option<String>,
+    pub key: Option<Vec<u8>>,
+    pub to_clipboard: bool,
+    pub wait: Option<util::Wait>,
+    /// Only pull these bytes of the file
+    pub range: Option<util::Range>,
+    /// Write the file here instead of stdout
+    pub out: Option<PathBuf>,
+    /// Continue a pull into out from where it stopped
+    pub resume: bool,
+    /// Write the file to the name it was pushed with
+    pub remote_name: bool,
+    /// Let --remote-name replace a file that's already there
+    pub clobber: bool,
 }
 
 #[derive(Default)]
@@ -248,69 +291,266 @@ pub struct BootstrapArgs {
     pub stdout: bool,
 }
 
+#[derive(Default)]
+pub struct QrArgs {
+    pub id: Option<String>,
+}
+
+#[derive(Default)]
+pub struct InfoArgs {
+    pub id: Option<String>,
+}
+
+/// A change to the attributes of a file that was already pushed
+#[derive(Default)]
+pub struct ChangeArgs {
+    pub id: Option<String>,
+    /// The file's password, needed when it has to be pulled and pushed again
+    pub current_pw: Option<String>,
+    pub lifetime: Option<util::Lifetime>,
+    pub private: Option<bool>,
+    /// The new password
+    pub pw: Option<String>,
+    pub gen_pw: bool,
+    /// What a copy that isn't private is checked with, unless secrets
+    /// are allowed
+    pub secrets: Option<secrets::Scanner>,
+}
+
+/// The account and endpoint of a profile in the config file
+#[derive(Default)]
+pub struct Profile {
+    pub name: String,
+    pub api_key: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+/// Profiles are named in the config file as [profile.NAME], and this one
+/// means the top level api_key and endpoint
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A copy of a file from one profile to another
+#[derive(Default)]
+pub struct CopyArgs {
+    /// SRC_PROFILE:ID, until the profiles are known
+    pub source: Option<String>,
+    pub id: Option<String>,
+    pub src: Profile,
+    pub dst: Option<Profile>,
+    /// The file's password, which the copy keeps unless a new one is given
+    pub current_pw: Option<String>,
+    pub lifetime: Option<util::Lifetime>,
+    pub private: Option<bool>,
+    pub burn: Option<bool>,
+    pub pw: Option<String>,
+    pub gen_pw: bool,
+    /// Copy files that are or might be burn files, which uses them up
+    pub allow_burn: bool,
+    /// What a copy that isn't private is checked with, unless secrets are allowed
+    pub secrets: Option<secrets::Scanner>,
+}
+
+#[derive(Default)]
+pub struct WatchArgs {
+    pub prefix: Option<util::Prefix>,
+    pub dir: Option<PathBuf>,
+    pub delete: Option<bool>,
+    /// Also pull the files that were there before the watcher started
+    pub existing: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct CompletionsArgs {
+    pub target: Option<completions::Target>,
+}
+
 pub fn try_get_args() -> Result<Args, ErrorKind> {
+    parse(lexopt::Parser::from_env())
+}
+
+fn parse(mut parser: lexopt::Parser) -> Result<Args, ErrorKind> {
     let mut opts = CommonOptions::default();
     let mut help = false;
 
     let mut command = None;
     let mut subcommand_name: Option<CommandName> = None;
 
-    let mut render_url = false;
     let mut pw = None;
+    let mut prefix = None;
+    let mut name: Option<util::Name> = None;
+    // each input, with the flag or config key it came from
+    let mut inputs = Vec::new();
     let mut push_args = PushArgs::default();
     let mut pull_args = PullArgs::default();
     let mut delete_args = DeleteArgs::default();
     let mut bootstrap_args = BootstrapArgs::default();
+    let mut completions_args = CompletionsArgs::default();
+    let mut qr_args = QrArgs::default();
+    let mut info_args = InfoArgs::default();
+    let mut change_args = ChangeArgs::default();
+    let mut copy_args = CopyArgs::default();
+    let mut dst_profile: Option<String> = None;
+    let mut no_hooks = false;
+    let mut watch_args = WatchArgs::default();
 
     use lexopt::prelude::*;
-    let mut parser = lexopt::Parser::from_env();
     while let Some(arg) = parser.next()? {
+        let flag = match &arg {
+            Long(name) => format!("--{}", name),
+            Short(name) => format!("-{}", name),
+            Value(_) => String::new(),
+        };
+        if !flag.is_empty() {
+            check_flag(&flag, subcommand_name.as_ref())?;
+        }
         match &arg {
             Short('h') | Long("help") => help = true,
 
-            Long("api-key") => opts.api_key = Some(parser.value()?.parse()?),
-            Long("endpoint") => opts.endpoint = Some(parser.value()?.parse()?),
-            Long("out-format") => opts.response_format = Some(parser.value()?.parse()?),
+            Long("api-key") => opts.api_key = Some(value(&mut parser, &flag)?),
+            Long("endpoint") => opts.endpoint = Some(value(&mut parser, &flag)?),
+            Long("out-format") => opts.response_format = Some(value(&mut parser, &flag)?),
+            Long("progress") => opts.progress = Some(value(&mut parser, &flag)?),
+            Long("limit-rate") => opts.limit_rate = Some(value(&mut parser, &flag)?),
+            Long("no-hooks") => no_hooks = true,
 
-            Long("lifetime") => push_args.lifetime = Some(parser.value()?.parse()?),
+            Long("lifetime") => push_args.lifetime = Some(value(&mut parser, &flag)?),
+            Long("split") => push_args.split = Some(value(&mut parser, &flag)?),
+            Long("content-type") => push_args.content_type = Some(value(&mut parser, &flag)?),
             Long("private") => push_args.private = Some(true),
             Long("no-private") => push_args.private = Some(false),
-            Long("pw") => pw = Some(parser.value()?.parse()?),
+            Long("pw") => pw = Some(util::PasswordSource::Literal(value(&mut parser, &flag)?)),
+            Long("pw-file") => pw = Some(util::PasswordSource::File(parser.value()?.into())),
+            Long("pw-env") => pw = Some(util::PasswordSource::Env(value(&mut parser, &flag)?)),
+            Long("pw-prompt") => pw = Some(util::PasswordSource::Prompt),
+            Long("gen-pw") => pw = Some(util::PasswordSource::Generate),
+            Long("current-pw") => {
+                let current_pw: String = value(&mut parser, &flag)?;
+                change_args.current_pw = Some(current_pw.clone());
+                copy_args.current_pw = Some(current_pw);
+            }
+            Long("allow-burn") => copy_args.allow_burn = true,
+            Long("pw-token") => push_args.pw_token = Some(true),
+            Long("no-pw-token") => push_args.pw_token = Some(false),
             Long("burn") => push_args.burn = Some(true),
             Long("no-burn") => push_args.burn = Some(false),
-            Long("prefix") => push_args.prefix = Some(parser.value()?.parse()?),
+            Long("prefix") => prefix = Some(value(&mut parser, &flag)?),
+            Long("name") => name = Some(value(&mut parser, &flag)?),
 
             // note: defer reading stdin to memory until all args are parsed
-            Long("stdin") => push_args.input = None,
-
+            Long("stdin") => inputs.push((util::InputSource::Stdin, "--stdin")),
             Long("file") => {
-                let name: String = parser.value()?.parse()?;
-                push_args.input = Some(util::InputMode::from_filename(name)?)
+                inputs.push((util::InputSource::File(parser.value()?.into()), "--file"))
+            }
+            Long("from-clipboard") => {
+                inputs.push((util::InputSource::Clipboard, "--from-clipboard"))
             }
+            Long("atomic") => push_args.atomic = Some(true),
+            Long("no-atomic") => push_args.atomic = Some(false),
+            Long("allow-secrets") => push_args.allow_secrets = Some(true),
+            Long("no-allow-secrets") => push_args.allow_secrets = Some(false),
+            Long("copy-id") => push_args.copy_id = Some(true),
+            Long("no-copy-id") => push_args.copy_id = Some(false),
 
             Long("anon") => pull_args.anon = Some(true),
             Long("no-anon") => pull_args.anon = Some(false),
+            Long("to-clipboard") => pull_args.to_clipboard = true,
+            Long("range") => {
+                if pull_args.range.is_some() {
+                    return Err(ErrorKind::CustomError(
+                        "--range and --head can't be used together".into(),
+                    ));
+                }
+                pull_args.range = Some(value(&mut parser, &flag)?);
+            }
+            Long("head") => {
+                if pull_args.range.is_some() {
+                    return Err(ErrorKind::CustomError(
+                        "--range and --head can't be used together".into(),
+                    ));
+                }
+                pull_args.range = Some(util::Range::head(value(&mut parser, &flag)?));
+            }
+            Short('o') | Long("out") => pull_args.out = Some(parser.value()?.into()),
+            Long("resume") => pull_args.resume = true,
+            Short('O') | Long("remote-name") => pull_args.remote_name = true,
+            Long("clobber") => pull_args.clobber = true,
+            Long("wait") => {
+                // the timeout is optional, so it can only be passed as --wait=TIMEOUT
+                pull_args.wait = Some(match parser.optional_value() {
+                    Some(raw) => raw.to_string_lossy().parse().map_err(|err: util::Error| {
+                        ErrorKind::InvalidValue(flag.clone(), err.to_string())
+                    })?,
+                    None => util::Wait::Forever,
+                })
+            }
+
+            Long("dir") => watch_args.dir = Some(parser.value()?.into()),
+            Long("delete") => watch_args.delete = Some(true),
+            Long("no-delete") => watch_args.delete = Some(false),
+            Long("existing") => watch_args.existing = Some(true),
+            Long("no-existing") => watch_args.existing = Some(false),
 
             Long("stdout") => bootstrap_args.stdout = true,
             Long("no-stdout") => bootstrap_args.stdout = false,
 
-            Long("url") => render_url = true,
-            Long("no-url") => render_url = false,
+            Long("share") => push_args.share = Some(true),
+            Long("no-share") => push_args.share = Some(false),
+            Long("encrypt") => push_args.encrypt = Some(true),
+            Long("no-encrypt") => push_args.encrypt = Some(false),
+
+            Long("url") => push_args.render_url = Some(true),
+            Long("no-url") => push_args.render_url = Some(false),
+            Long("emit") => push_args.emit = Some(value(&mut parser, &flag)?),
+            Long("emit-with-api-key") => push_args.emit_with_api_key = true,
+            Long("qr") => push_args.qr = Some(true),
+            Long("no-qr") => push_args.qr = Some(false),
 
             Value(subcommand) if subcommand_name.is_none() => {
                 if subcommand == "help" {
                     help = true;
                 } else {
-                    subcommand_name = Some(subcommand.parse()?)
+                    subcommand_name = Some(subcommand.to_string_lossy().parse()?)
                 }
             }
 
             Value(next_arg) => match &subcommand_name {
-                Some(x) => match x {
-                    CommandName::Pull => pull_args.id = Some(next_arg.parse()?),
-                    CommandName::Delete => delete_args.id = Some(next_arg.parse()?),
-                    _ => return Err(arg.unexpected().into()),
-                },
+                Some(name @ CommandName::Pull) if pull_args.id.is_none() => {
+                    pull_args.id = Some(positional(next_arg, name)?)
+                }
+                Some(name @ CommandName::Delete) if delete_args.id.is_none() => {
+                    delete_args.id = Some(positional(next_arg, name)?)
+                }
+                Some(name @ CommandName::Qr) if qr_args.id.is_none() => {
+                    qr_args.id = Some(positional(next_arg, name)?)
+                }
+                Some(name @ CommandName::Info) if info_args.id.is_none() => {
+                    info_args.id = Some(positional(next_arg, name)?)
+                }
+                Some(name @ (CommandName::Touch | CommandName::Chmod))
+                    if change_args.id.is_none() =>
+                {
+                    change_args.id = Some(positional(next_arg, name)?)
+                }
+                Some(name @ (CommandName::Copy | CommandName::Move))
+                    if copy_args.source.is_none() =>
+                {
+                    copy_args.source = Some(positional(next_arg, name)?)
+                }
+                Some(CommandName::Copy | CommandName::Move) if dst_profile.is_none() => {
+                    dst_profile = Some(next_arg.to_string_lossy().into())
+                }
+                Some(CommandName::Push) => {
+                    inputs.push((util::InputSource::File(next_arg.into()), "FILE"));
+                }
+                Some(name @ CommandName::Completions) if completions_args.target.is_none() => {
+                    completions_args.target = Some(positional(next_arg, name)?)
+                }
+                Some(name) => {
+                    return Err(ErrorKind::UnexpectedArgument(
+                        next_arg.to_string_lossy().into(),
+                        name.spec().name,
+                    ))
+                }
                 None => return Err(arg.unexpected().into()),
             },
             _ => return Err(arg.unexpected().into()),
@@ -323,40 +563,222 @@ pub fn try_get_args() -> Result<Args, ErrorKind> {
         }
     }
 
+    // --prefix names the prefix to push with, or the prefix to watch
+    match subcommand_name {
+        Some(CommandName::Watch) => watch_args.prefix = prefix,
+        _ => push_args.prefix = prefix,
+    }
+    // named files are stored with the prefix NAME|, see util::Name
+    if let Some(name) = name {
+        match subcommand_name {
+            Some(CommandName::Push) if push_args.prefix.is_some() => {
+                return Err(ErrorKind::CustomError(
+                    "--name can't be used with --prefix, the name is stored as the prefix".into(),
+                ))
+            }
+            Some(CommandName::Push) => {
+                push_args.prefix = Some(util::Prefix(name.prefix()));
+                push_args.name = Some(name);
+            }
+            _ if pull_args.id.is_some() => {
+                return Err(ErrorKind::CustomError(
+                    "--name can't be used with an ID, pull one or the other".into(),
+                ))
+            }
+            _ => pull_args.id = Some(name.prefix()),
+        }
+    }
+    let cli_private = push_args.private;
+    // touch and chmod only change what's on the command line, so they take
+    // these before the config file fills in push defaults
+    change_args.lifetime = push_args.lifetime.clone();
+    change_args.private = push_args.private;
+    copy_args.lifetime = push_args.lifetime.clone();
+    copy_args.private = push_args.private;
+    copy_args.burn = push_args.burn;
+    let mut profiles = BTreeMap::new();
+    let mut push_pw = None;
+    let mut pull_pw = None;
     match cf::load(cf::DEFAULT_CONFIG_PATH) {
         Ok(config_file) => {
+            for key in config_file.unknown_keys() {
+                eprintln!(
+                    "warning: unknown key '{}' in config file at {}",
+                    key,
+                    cf::DEFAULT_CONFIG_PATH
+                );
+            }
+            profiles = config_file.profiles;
             mv(config_file.api_key, &mut opts.api_key);
             mv(config_file.endpoint, &mut opts.endpoint);
+            if let Some(CommandName::List) = subcommand_name {
+                mv(config_file.list.format, &mut opts.response_format);
+            }
             mv(config_file.response.format, &mut opts.response_format);
+            mv(config_file.response.progress, &mut opts.progress);
+            mv(config_file.network.limit_rate, &mut opts.limit_rate);
+            if !no_hooks {
+                opts.hooks = hooks::Hooks {
+                    pre_push: config_file.hooks.pre_push,
+                    post_push: config_file.hooks.post_push,
+                    pre_pull: config_file.hooks.pre_pull,
+                    post_pull: config_file.hooks.post_pull,
+                };
+            }
 
             mv(config_file.push.lifetime, &mut push_args.lifetime);
             mv(config_file.push.private, &mut push_args.private);
             mv(config_file.push.burn, &mut push_args.burn);
             mv(config_file.push.prefix, &mut push_args.prefix);
+            mv(config_file.push.url, &mut push_args.render_url);
+            mv(config_file.push.share, &mut push_args.share);
+            mv(config_file.push.encrypt, &mut push_args.encrypt);
+            mv(config_file.push.emit, &mut push_args.emit);
+            mv(config_file.push.qr, &mut push_args.qr);
+            mv(config_file.push.copy_id, &mut push_args.copy_id);
+            if inputs.is_empty() {
+                if let Some(path) = config_file.push.file {
+                    let path = cf::expand_tilde(&path);
+                    inputs.push((util::InputSource::File(path), "scratch-push.file"));
+                }
+            }
+            mv(config_file.push.atomic, &mut push_args.atomic);
+            mv(config_file.push.split, &mut push_args.split);
+            mv(config_file.push.allow_secrets, &mut push_args.allow_secrets);
+            mv(config_file.push.content_type, &mut push_args.content_type);
+            for name in &config_file.secrets.disabled {
+                if !secrets::builtin_names().contains(&name.as_str()) {
+                    eprintln!(
+                        "warning: unknown secret rule '{}' in config file at {}",
+                        name,
+                        cf::DEFAULT_CONFIG_PATH
+                    );
+                }
+            }
+            push_args.secrets =
+                secrets::Scanner::new(&config_file.secrets.disabled, config_file.secrets.rules);
+            push_pw = util::PasswordSource::from_config(
+                config_file.push.pw,
+                config_file.push.pw_file.as_deref().map(cf::expand_tilde),
+                config_file.push.pw_env,
+                config_file.push.pw_prompt,
+            );
+            if let (None, Some(true)) = (&push_pw, config_file.push.gen_pw) {
+                push_pw = Some(util::PasswordSource::Generate);
+            }
+            mv(config_file.push.pw_token, &mut push_args.pw_token);
+
+            mv(config_file.watch.prefix, &mut watch_args.prefix);
+            if watch_args.dir.is_none() {
+                watch_args.dir = config_file.watch.dir.as_deref().map(cf::expand_tilde);
+            }
+            mv(config_file.watch.delete, &mut watch_args.delete);
+            mv(config_file.watch.existing, &mut watch_args.existing);
+
+            mv(config_file.pull.anon, &mut pull_args.anon);
+            pull_pw = util::PasswordSource::from_config(
+                config_file.pull.pw,
+                config_file.pull.pw_file.as_deref().map(cf::expand_tilde),
+                config_file.pull.pw_env,
+                config_file.pull.pw_prompt,
+            );
         }
         Err(err) => match err {
-            cf::ErrorKind::IoError(_) => {}
+            cf::ErrorKind::IoError(err) if err.kind() == io::ErrorKind::NotFound => {}
+            cf::ErrorKind::IoError(err) => {
+                return Err(ErrorKind::UnreadableConfigFile(
+                    cf::DEFAULT_CONFIG_PATH,
+                    err,
+                ))
+            }
             cf::ErrorKind::DeError(err) => {
                 return Err(ErrorKind::MalformedConfigFile(cf::DEFAULT_CONFIG_PATH, err))
             }
         },
     }
 
+    // copies that end up public are checked like pushes
+    let scanner = match push_args.allow_secrets {
+        Some(true) => None,
+        _ => Some(push_args.secrets.clone()),
+    };
+    change_args.secrets = scanner.clone();
+    copy_args.secrets = scanner;
+
     // set defaults, move subcommand args
     match &subcommand_name {
         Some(name) => match name {
             CommandName::Push => {
-                if push_args.input.is_none() && !help {
+                if !help {
                     // don't read from stdin when the user is asking for help
                     // with `scratch push --help`
-                    push_args.input = Some(util::InputMode::from_stdin()?);
+                    if inputs.is_empty() {
+                        inputs.push((util::InputSource::Stdin, "--stdin"));
+                    }
+                    if inputs.len() > 1 {
+                        if let Some((_, origin)) = inputs
+                            .iter()
+                            .find(|(source, _)| !matches!(source, util::InputSource::File(_)))
+                        {
+                            return Err(ErrorKind::CustomError(format!(
+                                "{} can't be combined with other inputs, only files can be pushed together",
+                                origin
+                            )));
+                        }
+                    }
+                    for (source, origin) in inputs {
+                        push_args.inputs.push(open_input(source, origin)?);
+                    }
+                }
+                if push_args.share.unwrap_or(false) {
+                    if let Some(true) = cli_private {
+                        return Err(ErrorKind::CustomError(
+                            "--share can't be used with --private, anyone with the token must be able to read the file".into(),
+                        ));
+                    }
+                    // share tokens are pulled anonymously, and protected by a password
+                    push_args.private = Some(false);
+                    if pw.is_none() && push_pw.is_none() {
+                        pw = Some(util::PasswordSource::Generate);
+                    }
+                }
+                if !help {
+                    let pw = pw.or(push_pw);
+                    push_args.gen_pw = matches!(pw, Some(util::PasswordSource::Generate));
+                    push_args.pw = resolve_pw(pw)?;
                 }
-                push_args.pw = pw;
-                push_args.render_url = render_url;
                 command = Some(Command::Push(push_args));
             }
             CommandName::Pull => {
-                pull_args.pw = pw;
+                resolve_reference(&mut pull_args.id, &mut opts.endpoint)?;
+                if let Some(token) = pull_args
+                    .id
+                    .as_deref()
+                    .filter(|id| ShareToken::is_token(id))
+                {
+                    // share tokens carry everything needed to pull the file
+                    let token: ShareToken = token.parse().map_err(|err: util::Error| {
+                        ErrorKind::InvalidValue("ID".into(), err.to_string())
+                    })?;
+                    opts.endpoint = Some(token.endpoint);
+                    pull_args.anon = Some(true);
+                    pull_args.id = Some(token.id);
+                    pull_args.key = token.key;
+                    pull_pw = None;
+                    if pw.is_none() {
+                        pw = token.pw.map(util::PasswordSource::Literal);
+                    }
+                }
+                if let (Some(id), None) = (&pull_args.id, &pw) {
+                    // accept ID:PASSWORD tokens from `push --gen-pw --pw-token`,
+                    // which take precedence over a password in the config file
+                    let (id, token_pw) = util::split_pw_token(id);
+                    pull_args.id = Some(id);
+                    pw = token_pw.map(util::PasswordSource::Literal);
+                }
+                if !help {
+                    pull_args.pw = resolve_pw(pw.or(pull_pw))?;
+                }
                 if let Some(true) = pull_args.anon {
                     // unset api_key when --anon
                     opts.api_key = None;
@@ -364,9 +786,93 @@ pub fn try_get_args() -> Result<Args, ErrorKind> {
                 command = Some(Command::Pull(pull_args))
             }
             CommandName::List => command = Some(Command::List),
-            CommandName::Delete => command = Some(Command::Delete(delete_args)),
+            CommandName::Delete => {
+                resolve_reference(&mut delete_args.id, &mut opts.endpoint)?;
+                command = Some(Command::Delete(delete_args))
+            }
             CommandName::Stats => command = Some(Command::Stats),
             CommandName::Bootstrap => command = Some(Command::Bootstrap(bootstrap_args)),
+            CommandName::Completions => command = Some(Command::Completions(completions_args)),
+            CommandName::Qr => {
+                resolve_reference(&mut qr_args.id, &mut opts.endpoint)?;
+                command = Some(Command::Qr(qr_args))
+            }
+            CommandName::Watch => command = Some(Command::Watch(watch_args)),
+            CommandName::History => command = Some(Command::History),
+            CommandName::Info => {
+                resolve_reference(&mut info_args.id, &mut opts.endpoint)?;
+                command = Some(Command::Info(info_args))
+            }
+            name @ (CommandName::Copy | CommandName::Move) => {
+                let profile = |name: &str| -> Result<Profile, ErrorKind> {
+                    if name == DEFAULT_PROFILE {
+                        return Ok(Profile {
+                            name: name.into(),
+                            api_key: opts.api_key.clone(),
+                            endpoint: opts.endpoint.clone(),
+                        });
+                    }
+                    let config: &cf::ProfileConfig = profiles.get(name).ok_or_else(|| {
+                        ErrorKind::CustomError(format!(
+                            "no profile named '{}', add a [profile.{}] section with an api_key and endpoint to {}",
+                            name, name, cf::DEFAULT_CONFIG_PATH
+                        ))
+                    })?;
+                    Ok(Profile {
+                        name: name.into(),
+                        api_key: config.api_key.clone(),
+                        endpoint: config.endpoint.clone(),
+                    })
+                };
+                if let Some(source) = copy_args.source.take() {
+                    // ids contain ':' too, so only a known profile name is
+                    // split off, and anything else is an id in the default
+                    let (name, id) = match source.split_once(':') {
+                        Some((name, id))
+                            if name == DEFAULT_PROFILE || profiles.contains_key(name) =>
+                        {
+                            (name.to_string(), id.to_string())
+                        }
+                        _ => (DEFAULT_PROFILE.to_string(), source),
+                    };
+                    copy_args.src = profile(&name)?;
+                    copy_args.id = Some(id);
+                    resolve_reference(&mut copy_args.id, &mut copy_args.src.endpoint)?;
+                    if let (Some(id), None) = (&copy_args.id, &copy_args.current_pw) {
+                        let (id, token_pw) = util::split_pw_token(id);
+                        copy_args.id = Some(id);
+                        copy_args.current_pw = token_pw;
+                    }
+                }
+                if let Some(name) = &dst_profile {
+                    copy_args.dst = Some(profile(name)?);
+                }
+                if !help {
+                    copy_args.gen_pw = matches!(pw, Some(util::PasswordSource::Generate));
+                    copy_args.pw = resolve_pw(pw)?;
+                }
+                command = Some(match name {
+                    CommandName::Copy => Command::Copy(copy_args),
+                    _ => Command::Move(copy_args),
+                })
+            }
+            name @ (CommandName::Touch | CommandName::Chmod) => {
+                resolve_reference(&mut change_args.id, &mut opts.endpoint)?;
+                if let (Some(id), None) = (&change_args.id, &change_args.current_pw) {
+                    // accept ID:PASSWORD tokens like pull does
+                    let (id, token_pw) = util::split_pw_token(id);
+                    change_args.id = Some(id);
+                    change_args.current_pw = token_pw;
+                }
+                if !help {
+                    change_args.gen_pw = matches!(pw, Some(util::PasswordSource::Generate));
+                    change_args.pw = resolve_pw(pw)?;
+                }
+                command = Some(match name {
+                    CommandName::Touch => Command::Touch(change_args),
+                    _ => Command::Chmod(change_args),
+                })
+            }
         },
         _ => {
             help = true;
@@ -375,18 +881,7 @@ pub fn try_get_args() -> Result<Args, ErrorKind> {
 
     if help {
         // replace command with Command::Help so caller can render it
-        let msg = match command {
-            Some(command) => match command {
-                Command::Help(_) => HELP,
-                Command::Push(_) => PUSH_HELP,
-                Command::Pull(_) => PULL_HELP,
-                Command::List => LIST_HELP,
-                Command::Delete(_) => DELETE_HELP,
-                Command::Stats => STATS_HELP,
-                Command::Bootstrap(_) => BOOTSTRAP_HELP,
-            },
-            None => HELP,
-        };
+        let msg = help::render(subcommand_name.as_ref().map(CommandName::spec));
         command = Some(Command::Help(msg));
     }
     let args = Args { opts, command };
@@ -397,6 +892,126 @@ pub fn try_get_args() -> Result<Args, ErrorKind> {
     Ok(args)
 }
 
+fn open_input(source: util::InputSource, origin: &'static str) -> Result<PushInput, ErrorKind> {
+    let (label, file_name, source_name) = match &source {
+        util::InputSource::File(path) => (
+            path.display().to_string(),
+            path.file_name().map(|name| name.to_string_lossy().into()),
+            std::fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+        ),
+        util::InputSource::Clipboard => ("clipboard".into(), None, "clipboard".into()),
+        util::InputSource::Stdin => ("stdin".into(), None, "stdin".into()),
+    };
+    let input = match source {
+        util::InputSource::File(path) => util::InputMode::from_filename(&path)
+            .map_err(|err| ErrorKind::UnreadableInput(origin, path, err))?,
+        util::InputSource::Clipboard => util::InputSource::Clipboard
+            .open()
+            .map_err(|err| ErrorKind::InvalidValue(origin.into(), err.to_string()))?,
+        stdin => stdin.open()?,
+    };
+    Ok(PushInput {
+        input,
+        label,
+        file_name,
+        source: source_name,
+    })
+}
+
+fn resolve_pw(source: Option<util::PasswordSource>) -> Result<Option<String>, ErrorKind> {
+    let source = match source {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+    let origin = match source {
+        util::PasswordSource::Literal(_) => "--pw",
+        util::PasswordSource::File(_) => "--pw-file",
+        util::PasswordSource::Env(_) => "--pw-env",
+        util::PasswordSource::Prompt => "--pw-prompt",
+        util::PasswordSource::Generate => "--gen-pw",
+    };
+    match source.resolve() {
+        Ok(pw) => Ok(Some(pw)),
+        Err(err) => Err(ErrorKind::InvalidValue(origin.into(), err.to_string())),
+    }
+}
+
+/// Replace an @N reference with the id and endpoint from the history file
+fn resolve_reference(
+    id: &mut Option<String>,
+    endpoint: &mut Option<String>,
+) -> Result<(), ErrorKind> {
+    if let Some(reference) = id.as_deref().filter(|id| history::is_reference(id)) {
+        let entry = history::resolve(reference)
+            .map_err(|err| ErrorKind::InvalidValue("ID".into(), err.to_string()))?;
+        *id = Some(entry.id);
+        *endpoint = Some(entry.endpoint);
+    }
+    Ok(())
+}
+
+/// Parse the value of a flag, naming the flag when the value is malformed
+fn value<T>(parser: &mut lexopt::Parser, flag: &str) -> Result<T, ErrorKind>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let raw = parser.value()?;
+    let text = raw.to_string_lossy();
+    text.parse()
+        .map_err(|err: T::Err| ErrorKind::InvalidValue(flag.into(), err.to_string()))
+}
+
+/// Parse a command's positional argument, naming the argument when it's malformed
+fn positional<T>(raw: &std::ffi::OsStr, command: &CommandName) -> Result<T, ErrorKind>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let name = match command.spec().positionals.first() {
+        Some(positional) => positional.name,
+        None => "argument",
+    };
+    raw.to_string_lossy()
+        .parse()
+        .map_err(|err: T::Err| ErrorKind::InvalidValue(name.into(), err.to_string()))
+}
+
+/// Reject flags that don't exist, or don't apply to the chosen command
+fn check_flag(flag: &str, command: Option<&CommandName>) -> Result<(), ErrorKind> {
+    if spec::is_global(flag) {
+        return Ok(());
+    }
+    let command = command.map(CommandName::spec);
+    if let Some(command) = command {
+        if command.accepts(flag) {
+            return Ok(());
+        }
+    }
+    let owners = spec::commands_accepting(flag);
+    if !owners.is_empty() {
+        return Err(ErrorKind::MisplacedFlag(
+            flag.into(),
+            command.map(|c| c.name),
+            owners,
+        ));
+    }
+    let candidates = match command {
+        Some(command) => command.spellings(),
+        None => spec::GLOBAL_FLAGS
+            .iter()
+            .flat_map(|f| f.spellings())
+            .collect(),
+    };
+    Err(ErrorKind::UnknownFlag(
+        flag.into(),
+        spec::suggest(flag, candidates),
+    ))
+}
+
 fn validate_args(args: &Args) -> Result<(), ErrorKind> {
     if args.opts.api_key.is_none() {
         match &args.command {
@@ -408,8 +1023,16 @@ fn validate_args(args: &Args) -> Result<(), ErrorKind> {
                         return Err(ErrorKind::MissingArgument("--api-key", "api_key"));
                     }
                 }
-                Command::Bootstrap(_) => {
-                    // bootstrapping doesn't require api key
+                Command::Bootstrap(_) | Command::Completions(_) => {
+                    // bootstrapping doesn't require api key, and completions
+                    // can't report errors to the user
+                }
+                Command::Copy(_) | Command::Move(_) => {
+                    // each profile is checked for its own api key
+                }
+                Command::Qr(_) | Command::History => {
+                    // the url is built from the endpoint alone, and the
+                    // history is local
                 }
                 _ => return Err(ErrorKind::MissingArgument("--api-key", "api_key")),
             },
@@ -417,36 +1040,232 @@ fn validate_args(args: &Args) -> Result<(), ErrorKind> {
         }
     }
     if args.opts.endpoint.is_none() {
-        match &args.command {
-            Some(command) => match command {
-                Command::Bootstrap(_) => {
-                    // bootstrapping doesn't require endpoint
+        if let Some(command) = &args.command {
+            match command {
+                Command::Bootstrap(_)
+                | Command::Completions(_)
+                | Command::History
+                | Command::Copy(_)
+                | Command::Move(_) => {
+                    // bootstrapping doesn't require endpoint, and the history is local
                 }
                 _ => {
                     return Err(ErrorKind::MissingArgument("--endpoint", "endpoint"));
                 }
-            },
-            None => {}
+            }
         }
     }
     match &args.command {
-        Some(x) => match x {
-            Command::Delete(args) => {
-                if args.id.is_none() {
-                    return Err(ErrorKind::MissingPositionalArgument("ID"));
-                }
+        Some(Command::Delete(args)) if args.id.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("ID"));
+        }
+        Some(Command::Push(args)) if args.encrypt == Some(true) && args.share != Some(true) => {
+            return Err(ErrorKind::CustomError(
+                "--encrypt requires --share, the key is only stored in the share token".into(),
+            ));
+        }
+        Some(Command::Push(args)) if args.emit.is_some() && args.share == Some(true) => {
+            return Err(ErrorKind::CustomError(
+                "--emit can't be used with --share, pick one way to hand out the file".into(),
+            ));
+        }
+        Some(Command::Push(args))
+            if args.emit.is_some() && args.private != Some(false) && !args.emit_with_api_key =>
+        {
+            return Err(ErrorKind::CustomError(
+                "--emit on a private file would put your api key in the command, push with --no-private or pass --emit-with-api-key".into(),
+            ));
+        }
+        Some(Command::Push(args)) if args.emit.is_some() && args.split.is_some() => {
+            return Err(ErrorKind::CustomError(
+                "--emit can't be used with --split, the download command would only fetch the manifest".into(),
+            ));
+        }
+        Some(Command::Push(args)) if args.inputs.len() > 1 => {
+            let single = [
+                ("--share", args.share == Some(true)),
+                ("--encrypt", args.encrypt == Some(true)),
+                ("--emit", args.emit.is_some()),
+                ("--qr", args.qr == Some(true)),
+                ("--copy-id", args.copy_id == Some(true)),
+                ("--name", args.name.is_some()),
+                ("--split", args.split.is_some()),
+            ];
+            if let Some((flag, _)) = single.iter().find(|(_, set)| *set) {
+                return Err(ErrorKind::CustomError(format!(
+                    "{} only works when pushing one file",
+                    flag
+                )));
+            }
+        }
+        Some(Command::Pull(pull)) if pull.resume && pull.out.is_none() && !pull.remote_name => {
+            return Err(ErrorKind::CustomError(
+                "--resume needs --out, it continues the file that's already there".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.clobber && !pull.remote_name => {
+            return Err(ErrorKind::CustomError(
+                "--clobber only works with --remote-name, --out always replaces the file".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.remote_name && pull.out.is_some() => {
+            return Err(ErrorKind::CustomError(
+                "--remote-name can't be used with --out, pick one file to write".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.remote_name && pull.to_clipboard => {
+            return Err(ErrorKind::CustomError(
+                "--remote-name can't be used with --to-clipboard".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.remote_name && pull.wait.is_some() => {
+            return Err(ErrorKind::CustomError(
+                "--remote-name can't be used with --wait, the name isn't known until the file exists".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.resume && pull.wait.is_some() => {
+            return Err(ErrorKind::CustomError(
+                "--resume can't be used with --wait, it continues a file that's already been pushed".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.resume && pull.range.is_some() => {
+            return Err(ErrorKind::CustomError(
+                "--resume can't be used with --range or --head".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if pull.out.is_some() && pull.to_clipboard => {
+            return Err(ErrorKind::CustomError(
+                "--out can't be used with --to-clipboard".into(),
+            ));
+        }
+        Some(Command::Pull(pull))
+            if pull.key.is_some() && (pull.range.is_some() || pull.resume) =>
+        {
+            return Err(ErrorKind::CustomError(
+                "an encrypted share can only be pulled whole, it's checked and decrypted in one piece".into(),
+            ));
+        }
+        Some(Command::Pull(pull)) if args.opts.api_key.is_none() => {
+            let waits_for_prefix = |id: &&str| pull.wait.is_some() && util::is_wait_prefix(id);
+            if let Some(id) = pull
+                .id
+                .as_deref()
+                .filter(|id| util::is_name_prefix(id) || waits_for_prefix(id))
+            {
+                return Err(ErrorKind::CustomError(format!(
+                    "finding the newest file for '{}' lists your files, which needs an api key",
+                    id
+                )));
             }
-            Command::Bootstrap(args) => {
-                if !args.stdout && cf::exists(cf::DEFAULT_CONFIG_PATH) {
+        }
+        Some(Command::Qr(args)) if args.id.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("ID"));
+        }
+        Some(Command::Info(args)) if args.id.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("ID"));
+        }
+        Some(Command::Copy(args) | Command::Move(args)) if args.id.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("SRC_PROFILE:ID"));
+        }
+        Some(Command::Copy(args) | Command::Move(args)) if args.dst.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("DST_PROFILE"));
+        }
+        Some(Command::Copy(args) | Command::Move(args)) => {
+            for profile in [Some(&args.src), args.dst.as_ref()].into_iter().flatten() {
+                if profile.api_key.is_none() || profile.endpoint.is_none() {
                     return Err(ErrorKind::CustomError(format!(
-                        "error: existing config file found at {}",
-                        cf::DEFAULT_CONFIG_PATH
+                        "profile '{}' needs both an api_key and an endpoint",
+                        profile.name
                     )));
                 }
             }
-            _ => {}
-        },
+        }
+        Some(Command::Touch(args) | Command::Chmod(args)) if args.id.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("ID"));
+        }
+        Some(Command::Touch(args)) if args.lifetime.is_none() => {
+            return Err(ErrorKind::CustomError(
+                "touch needs --lifetime, the new lifetime of the file counted from now".into(),
+            ));
+        }
+        Some(Command::Chmod(args)) if args.private.is_none() && args.pw.is_none() => {
+            return Err(ErrorKind::CustomError(
+                "chmod needs --private, --no-private or a new password".into(),
+            ));
+        }
+        Some(Command::Completions(args)) if args.target.is_none() => {
+            return Err(ErrorKind::MissingPositionalArgument("SHELL"));
+        }
+        Some(Command::Bootstrap(args)) if !args.stdout && cf::exists(cf::DEFAULT_CONFIG_PATH) => {
+            return Err(ErrorKind::CustomError(format!(
+                "error: existing config file found at {}",
+                cf::DEFAULT_CONFIG_PATH
+            )));
+        }
         _ => {}
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a command line with --help, so nothing is read or prompted for
+    fn parse_help(command: &str, flag: &str) -> Result<Args, ErrorKind> {
+        parse(lexopt::Parser::from_args([
+            command.to_string(),
+            "--help".into(),
+            flag.into(),
+        ]))
+    }
+
+    /// Flags missing from spec are rejected before they're matched, so every
+    /// flag the parser takes is documented.  This checks the other way: that
+    /// every spelling in spec, short ones included, is taken by the parser,
+    /// and takes a value exactly when spec says it does.
+    #[test]
+    fn every_documented_flag_is_parsed() {
+        for command in spec::COMMANDS {
+            let flags = spec::GLOBAL_FLAGS.iter().chain(command.flags.iter());
+            for flag in flags {
+                let mut spellings = vec![format!("--{}", flag.long)];
+                if let Some(value) = flag.value.filter(|_| !flag.value_optional) {
+                    spellings[0] = format!("--{}={}", flag.long, value);
+                }
+                if flag.negatable {
+                    spellings.push(format!("--no-{}", flag.long));
+                }
+                if let Some(short) = flag.short {
+                    match flag.value {
+                        Some(value) => spellings.push(format!("-{}{}", short, value)),
+                        None => spellings.push(format!("-{}", short)),
+                    }
+                }
+                for spelling in spellings {
+                    let parsed = parse_help(command.name, &spelling);
+                    assert!(
+                        !matches!(
+                            parsed,
+                            Err(ErrorKind::Lexopt(_)
+                                | ErrorKind::UnknownFlag(..)
+                                | ErrorKind::MisplacedFlag(..))
+                        ),
+                        "scratch {} {} is described in spec but not parsed: {}",
+                        command.name,
+                        spelling,
+                        parsed.err().unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn undocumented_flags_are_rejected() {
+        let parsed = parse_help("push", "--no-such-flag");
+        assert!(matches!(parsed, Err(ErrorKind::UnknownFlag(..))));
+        let parsed = parse_help("push", "-O");
+        assert!(matches!(parsed, Err(ErrorKind::MisplacedFlag(..))));
+    }
+}
@@ -0,0 +1,190 @@
+use super::api::{self, ClientOpts, DeleteArgs, InfoArgs, PullArgs, PushArgs, UpdateArgs};
+use super::history;
+use super::info::Info;
+use super::secrets::Scanner;
+use super::util::{InputMode, Lifetime, Prefix};
+
+/// New attributes for a pushed file.  Attributes that are None stay as
+/// they are.
+pub struct Change {
+    pub lifetime: Option<Lifetime>,
+    pub private: Option<bool>,
+    pub pw: Option<String>,
+}
+
+/// Where a changed file ended up
+pub struct Changed {
+    pub id: String,
+    /// The old id, when the file had to be pushed again under a new one
+    pub replaced: Option<String>,
+}
+
+/// Change a file's attributes, in place if the server can do that, or
+/// otherwise by pulling it and pushing it again with the new attributes,
+/// then deleting the old file.  The history follows the file either way.
+/// A copy that isn't private is checked with secrets first, if given.
+pub fn apply(
+    id: &str,
+    current_pw: Option<String>,
+    change: Change,
+    secrets: Option<&Scanner>,
+    api_key: &str,
+    endpoint: &str,
+    opts: ClientOpts,
+) -> Result<Changed, api::ErrorKind> {
+    let update = UpdateArgs::new(
+        api_key.into(),
+        endpoint.into(),
+        id.into(),
+        current_pw.clone(),
+        change.lifetime.clone(),
+        change.private,
+        change.pw.clone(),
+    );
+    if api::update(update, opts)? {
+        let _ = history::update(id, |entry| {
+            if let Some(lifetime) = &change.lifetime {
+                entry.expires = history::expiry(Some(lifetime));
+                entry.lifetime = Some(lifetime.0.clone());
+            }
+            if let Some(private) = change.private {
+                entry.private = Some(private);
+            }
+        });
+        return Ok(Changed {
+            id: id.into(),
+            replaced: None,
+        });
+    }
+    copy(id, current_pw, change, secrets, api_key, endpoint, opts)
+}
+
+fn copy(
+    id: &str,
+    current_pw: Option<String>,
+    change: Change,
+    secrets: Option<&Scanner>,
+    api_key: &str,
+    endpoint: &str,
+    opts: ClientOpts,
+) -> Result<Changed, api::ErrorKind> {
+    let info = lookup(id, api_key, endpoint, opts)?;
+    // pulling a burn file to copy it would use up its one download
+    match info.burn {
+        Some(false) => {}
+        Some(true) => {
+            return Err(api::ErrorKind::ServerError(
+                "the server can't change files in place, and copying a burn file would consume it",
+            ))
+        }
+        None => {
+            return Err(api::ErrorKind::ServerError(
+                "the server can't change files in place, and copying could consume the file if it's a burn file, which is unknown",
+            ))
+        }
+    }
+    let source = info.source.clone();
+    let lifetime = change.lifetime.or_else(|| remaining_lifetime(&info));
+    let private = change.private.or(info.private);
+    let pw = change.pw.or_else(|| current_pw.clone());
+
+    let mut data = Vec::new();
+    let pull = PullArgs::new(
+        endpoint.into(),
+        Some(id.into()),
+        Some(api_key.into()),
+        current_pw,
+        None,
+        &mut data,
+    );
+    api::pull(pull, opts)?;
+
+    let mut input = InputMode::Buffer(data);
+    if let Some(secrets) = secrets.filter(|_| private != Some(true)) {
+        secrets
+            .check(&mut input)
+            .map_err(|err| api::ErrorKind::Rejected(err.to_string()))?;
+    }
+    let (size, sha256) = (
+        input.size(),
+        input.sha256().map_err(api::ErrorKind::LocalIoError)?,
+    );
+    let push = PushArgs::new(
+        api_key.into(),
+        endpoint.into(),
+        input,
+        info.burn,
+        private,
+        pw,
+        prefix(id),
+        lifetime.clone(),
+    )
+    .file(info.file_name.clone(), info.content_type.clone());
+    let mut new_id = None;
+    api::push(push, opts, |id, _| new_id = Some(id.to_string()))?;
+    let new_id = new_id.ok_or(api::ErrorKind::ServerError(
+        "malformed resp from server: no id",
+    ))?;
+
+    let delete = DeleteArgs::new(api_key.into(), endpoint.into(), id.into());
+    if let Err(err) = api::delete(delete, opts) {
+        eprintln!("unable to delete {} after copying it: {}", id, err);
+    }
+    let _ = history::forget(id);
+    if let Some(source) = source {
+        let _ = history::record(history::Entry {
+            id: new_id.clone(),
+            endpoint: endpoint.into(),
+            pushed: history::now(),
+            expires: history::expiry(lifetime.as_ref()),
+            size,
+            sha256,
+            lifetime: lifetime.map(|lifetime| lifetime.0),
+            burn: info.burn,
+            private,
+            source,
+            file_name: info.file_name,
+            content_type: info.content_type,
+        });
+    }
+    Ok(Changed {
+        id: new_id,
+        replaced: Some(id.into()),
+    })
+}
+
+/// What's known about a file from the server and the push history, which
+/// a copy of it starts from
+pub fn lookup(
+    id: &str,
+    api_key: &str,
+    endpoint: &str,
+    opts: ClientOpts,
+) -> Result<Info, api::ErrorKind> {
+    let info_args = InfoArgs::new(api_key.into(), endpoint.into(), id.into());
+    let mut info = api::info(info_args, opts)?;
+    if !info.exists {
+        return Err(api::ErrorKind::NoSuchName(id.into()));
+    }
+    let pushed = history::list()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|entry| entry.id == id && entry.endpoint == endpoint);
+    if let Some(entry) = pushed {
+        info.fill_from(entry);
+    }
+    Ok(info)
+}
+
+/// How long a file has left, as a lifetime for a copy of it
+pub fn remaining_lifetime(info: &Info) -> Option<Lifetime> {
+    let left = info.expires?.checked_sub(history::now())?;
+    Some(Lifetime(format!("{}s", left.max(1))))
+}
+
+/// The prefix of an id, up to and including its last ':' or '|', so a copy
+/// can still be found by the prefix or name it was pushed with
+pub fn prefix(id: &str) -> Option<Prefix> {
+    let end = id.rfind([':', '|'])?;
+    Some(Prefix(id[..=end].into()))
+}
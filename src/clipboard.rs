@@ -0,0 +1,106 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// An external program that reads or writes the system clipboard
+struct Tool {
+    copy: &'static [&'static str],
+    paste: &'static [&'static str],
+}
+
+const WAYLAND: Tool = Tool {
+    copy: &["wl-copy"],
+    paste: &["wl-paste", "--no-newline"],
+};
+const X11: Tool = Tool {
+    copy: &["xclip", "-selection", "clipboard", "-in"],
+    paste: &["xclip", "-selection", "clipboard", "-out"],
+};
+const MACOS: Tool = Tool {
+    copy: &["pbcopy"],
+    paste: &["pbpaste"],
+};
+
+/// Clipboard programs that can work in the current session, best first
+fn tools() -> Vec<&'static Tool> {
+    let mut tools = Vec::new();
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.push(&WAYLAND);
+    }
+    if env::var_os("DISPLAY").is_some() {
+        tools.push(&X11);
+    }
+    if cfg!(target_os = "macos") {
+        tools.push(&MACOS);
+    }
+    tools
+}
+
+fn run(argv: &[&str], input: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let mut child = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(match input {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(match input {
+            // wl-copy and xclip fork to serve the selection, and keep any
+            // inherited stdout open while they do
+            Some(_) => Stdio::null(),
+            None => Stdio::piped(),
+        })
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input)?;
+    }
+    let output = child.wait_with_output()?;
+    match output.status.success() {
+        true => Ok(output.stdout),
+        false => Err(io::Error::other(format!("{} failed", argv[0]))),
+    }
+}
+
+/// Read the clipboard with the first clipboard program that's installed
+pub fn read() -> io::Result<Vec<u8>> {
+    for tool in tools() {
+        match run(tool.paste, None) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard found, install wl-clipboard (wayland) or xclip (x11)",
+    ))
+}
+
+/// Write to the clipboard with the first clipboard program that's
+/// installed, or ask the terminal to do it with an OSC 52 escape sequence.
+/// OSC 52 works over ssh, but not every terminal supports it.  The escape
+/// sequence carries the data, so it's only sent to a terminal, never to a
+/// log or a file.
+pub fn write(data: &[u8]) -> io::Result<()> {
+    for tool in tools() {
+        match run(tool.copy, Some(data)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            result => return result.map(|_| ()),
+        }
+    }
+    // stdout may be redirected, the terminal is still listening on stderr
+    let mut stderr = io::stderr();
+    if !stderr.is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no clipboard found, install wl-copy (wayland), xclip (x11) or pbcopy (macos)",
+        ));
+    }
+    write!(
+        stderr,
+        "\x1b]52;c;{}\x07",
+        base64::encode_config(data, base64::STANDARD)
+    )?;
+    stderr.flush()
+}
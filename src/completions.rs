@@ -0,0 +1,331 @@
+use core::str::FromStr;
+use std::fmt::Write;
+
+use super::help;
+use super::spec::{self, Complete, Flag};
+use super::util;
+
+pub enum Target {
+    Bash,
+    Zsh,
+    Fish,
+    /// Used by the generated scripts to complete file ids
+    Ids,
+    /// The scratch(1) man page, for packagers
+    Man,
+}
+
+impl FromStr for Target {
+    type Err = util::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Target::Bash),
+            "zsh" => Ok(Target::Zsh),
+            "fish" => Ok(Target::Fish),
+            "ids" => Ok(Target::Ids),
+            "man" => Ok(Target::Man),
+            _ => Err(util::Error::MalformedArgument(
+                "shell",
+                s.into(),
+                "one of bash, zsh, fish".into(),
+            )),
+        }
+    }
+}
+
+/// Render the completion script for a shell, or the man page; None for
+/// Target::Ids
+pub fn script(target: &Target) -> Option<String> {
+    match target {
+        Target::Bash => Some(bash()),
+        Target::Zsh => Some(zsh()),
+        Target::Fish => Some(fish()),
+        Target::Man => Some(help::man_page()),
+        Target::Ids => None,
+    }
+}
+
+const IDS_COMMAND: &str = "scratch completions ids 2>/dev/null";
+
+/// First sentence of a help string, which is short enough for a completion menu
+fn summary(help: &str) -> &str {
+    let end = help.find(".  ").unwrap_or(help.len());
+    help[..end].trim_end_matches('.')
+}
+
+fn all_flags(command: &spec::Command) -> impl Iterator<Item = &Flag> {
+    spec::GLOBAL_FLAGS.iter().chain(command.flags.iter())
+}
+
+fn bash() -> String {
+    let mut out = String::new();
+    let mut value_flags = spec::GLOBAL_FLAGS
+        .iter()
+        .chain(spec::COMMANDS.iter().flat_map(|c| c.flags.iter()))
+        .filter(|f| f.value.is_some() && !f.value_optional)
+        .map(|f| format!("--{}", f.long))
+        .collect::<Vec<_>>();
+    value_flags.sort();
+    value_flags.dedup();
+    let value_flags_pattern = value_flags.join("|");
+
+    let _ = writeln!(out, "_scratch() {{");
+    let _ = writeln!(out, "    local cur prev cmd i");
+    let _ = writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(out, "    cmd=\"\"");
+    let _ = writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do");
+    let _ = writeln!(out, "        case \"${{COMP_WORDS[i]}}\" in");
+    let _ = writeln!(out, "            {}) ((i++)) ;;", value_flags_pattern);
+    let _ = writeln!(out, "            -*) ;;");
+    let _ = writeln!(out, "            *) cmd=\"${{COMP_WORDS[i]}}\"; break ;;");
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "    case \"$prev\" in");
+    let value_completions = spec::GLOBAL_FLAGS
+        .iter()
+        .chain(spec::COMMANDS.iter().flat_map(|c| c.flags.iter()))
+        .filter(|f| f.value.is_some() && !f.value_optional);
+    let mut seen = Vec::new();
+    for flag in value_completions {
+        if seen.contains(&flag.long) {
+            continue;
+        }
+        seen.push(flag.long);
+        let reply = bash_reply(flag.complete);
+        let _ = writeln!(out, "        --{}) {}; return ;;", flag.long, reply);
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+
+    let globals = spec::GLOBAL_FLAGS
+        .iter()
+        .flat_map(|f| f.spellings())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let commands = spec::COMMANDS
+        .iter()
+        .map(|c| c.name)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let _ = writeln!(out, "    case \"$cmd\" in");
+    let _ = writeln!(
+        out,
+        "        \"\") COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\")) ;;",
+        globals, commands
+    );
+    for command in spec::COMMANDS {
+        let flags = all_flags(command)
+            .flat_map(|f| f.spellings())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(out, "        {})", command.name);
+        let _ = writeln!(out, "            if [[ \"$cur\" == -* ]]; then");
+        let _ = writeln!(
+            out,
+            "                COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            flags
+        );
+        // bash doesn't know which argument is being completed, so use the first
+        if let Some(positional) = command.positionals.first() {
+            let _ = writeln!(out, "            else");
+            let _ = writeln!(out, "                {}", bash_reply(positional.complete));
+        }
+        let _ = writeln!(out, "            fi ;;");
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "complete -F _scratch scratch");
+    out
+}
+
+fn bash_reply(complete: Complete) -> String {
+    match complete {
+        Complete::Nothing => "COMPREPLY=()".into(),
+        Complete::Path => "COMPREPLY=($(compgen -f -- \"$cur\"))".into(),
+        Complete::FileIds => format!(
+            "COMPREPLY=($(compgen -W \"$({})\" -- \"$cur\"))",
+            IDS_COMMAND
+        ),
+        Complete::Choices(choices) => format!(
+            "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            choices.join(" ")
+        ),
+    }
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_action(complete: Complete) -> String {
+    match complete {
+        Complete::Nothing => "".into(),
+        Complete::Path => "_files".into(),
+        Complete::FileIds => "_scratch_ids".into(),
+        Complete::Choices(choices) => format!("({})", choices.join(" ")),
+    }
+}
+
+fn zsh_flag_specs(command: Option<&spec::Command>) -> Vec<String> {
+    let flags: Vec<&Flag> = match command {
+        Some(command) => all_flags(command).collect(),
+        None => spec::GLOBAL_FLAGS.iter().collect(),
+    };
+    let mut specs = Vec::new();
+    for flag in flags {
+        let help = zsh_escape(summary(flag.help));
+        let value = match flag.value {
+            Some(name) => format!(":{}:{}", name, zsh_action(flag.complete)),
+            None => "".into(),
+        };
+        let attach = match flag.value_optional {
+            true => "=-",
+            false => "",
+        };
+        specs.push(format!("'--{}{}[{}]{}'", flag.long, attach, help, value));
+        if let Some(short) = flag.short {
+            specs.push(format!("'-{}[{}]{}'", short, help, value));
+        }
+        if flag.negatable {
+            specs.push(format!("'--no-{}[{}]'", flag.long, help));
+        }
+    }
+    let positionals = command.map_or(&[][..], |c| c.positionals);
+    for (i, positional) in positionals.iter().enumerate() {
+        specs.push(format!(
+            "'{}:{}:{}'",
+            match positional.repeated {
+                true => "*".into(),
+                false => (i + 1).to_string(),
+            },
+            zsh_escape(summary(positional.help)),
+            zsh_action(positional.complete)
+        ));
+    }
+    specs
+}
+
+fn zsh() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "#compdef scratch");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_scratch_ids() {{");
+    let _ = writeln!(out, "    local -a ids");
+    let _ = writeln!(out, "    ids=(${{(f)\"$({})\"}})", IDS_COMMAND);
+    let _ = writeln!(out, "    _describe 'file id' ids");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_scratch() {{");
+    let _ = writeln!(out, "    local line state");
+    let _ = writeln!(out, "    _arguments -C \\");
+    for spec in zsh_flag_specs(None) {
+        let _ = writeln!(out, "        {} \\", spec);
+    }
+    let _ = writeln!(out, "        '1: :->command' \\");
+    let _ = writeln!(out, "        '*:: :->args'");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    case $state in");
+    let _ = writeln!(out, "        command)");
+    let _ = writeln!(out, "            local -a commands");
+    let _ = writeln!(out, "            commands=(");
+    for command in spec::COMMANDS {
+        let _ = writeln!(
+            out,
+            "                '{}:{}'",
+            command.name,
+            zsh_escape(command.summary)
+        );
+    }
+    let _ = writeln!(out, "            )");
+    let _ = writeln!(out, "            _describe command commands ;;");
+    let _ = writeln!(out, "        args)");
+    let _ = writeln!(out, "            case $line[1] in");
+    for command in spec::COMMANDS {
+        let _ = writeln!(out, "                {})", command.name);
+        let _ = writeln!(out, "                    _arguments \\");
+        let specs = zsh_flag_specs(Some(command));
+        for (i, spec) in specs.iter().enumerate() {
+            let cont = if i + 1 < specs.len() { " \\" } else { " ;;" };
+            let _ = writeln!(out, "                        {}{}", spec, cont);
+        }
+    }
+    let _ = writeln!(out, "            esac ;;");
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_scratch \"$@\"");
+    out
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish_flag(out: &mut String, condition: &str, flag: &Flag) {
+    let help = fish_escape(summary(flag.help));
+    let mut line = format!("complete -c scratch{} -l {}", condition, flag.long);
+    if let Some(short) = flag.short {
+        let _ = write!(line, " -s {}", short);
+    }
+    // fish can't complete a value that has to be attached with =
+    if flag.value.is_some() && !flag.value_optional {
+        match flag.complete {
+            Complete::Path => line.push_str(" -r -F"),
+            Complete::Choices(choices) => {
+                let _ = write!(line, " -x -a '{}'", choices.join(" "));
+            }
+            _ => line.push_str(" -x"),
+        }
+    }
+    let _ = writeln!(out, "{} -d '{}'", line, help);
+    if flag.negatable {
+        let _ = writeln!(
+            out,
+            "complete -c scratch{} -l no-{} -d '{}'",
+            condition, flag.long, help
+        );
+    }
+}
+
+fn fish() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "function __scratch_ids");
+    let _ = writeln!(out, "    {}", IDS_COMMAND);
+    let _ = writeln!(out, "end");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "complete -c scratch -f");
+    for flag in spec::GLOBAL_FLAGS {
+        fish_flag(&mut out, "", flag);
+    }
+    for command in spec::COMMANDS {
+        let _ = writeln!(
+            out,
+            "complete -c scratch -n '__fish_use_subcommand' -a {} -d '{}'",
+            command.name,
+            fish_escape(command.summary)
+        );
+    }
+    for command in spec::COMMANDS {
+        let condition = format!(" -n '__fish_seen_subcommand_from {}'", command.name);
+        for flag in command.flags {
+            fish_flag(&mut out, &condition, flag);
+        }
+        for positional in command.positionals {
+            let args = match positional.complete {
+                Complete::Nothing => continue,
+                Complete::Path => " -F".into(),
+                Complete::FileIds => " -a '(__scratch_ids)'".into(),
+                Complete::Choices(choices) => format!(" -a '{}'", choices.join(" ")),
+            };
+            let _ = writeln!(out, "complete -c scratch{}{}", condition, args);
+        }
+    }
+    out
+}
@@ -4,12 +4,14 @@ use serde::{
     Deserialize,
 };
 use std::{
+    collections::BTreeMap,
     fs, io,
     path::{Path, PathBuf},
 };
-use toml;
 
-use super::util::{Lifetime, Prefix, ResponseFormat};
+use super::emit::Emit;
+use super::secrets::Pattern;
+use super::util::{ContentType, Lifetime, Prefix, Progress, ResponseFormat, Size};
 
 pub const DEFAULT_CONFIG_PATH: &str = "~/.kilobytetools/config.toml";
 
@@ -31,9 +33,8 @@ pub fn write(config_path: &str, data: String) -> io::Result<()> {
     Ok(())
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
-    if path.starts_with("~") {
-        let rest = &path[1..];
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
         if rest.is_empty() || rest.starts_with("/") {
             let home = dirs_next::home_dir();
             return Path::new(format!("{}{}", home.unwrap().display(), rest).as_str()).into();
@@ -68,14 +69,98 @@ pub struct ConfigFile {
     #[serde(default, rename = "response")]
     pub response: ResponseConfig,
 
+    #[serde(default, rename = "network")]
+    pub network: NetworkConfig,
+
+    #[serde(default, rename = "hooks")]
+    pub hooks: HooksConfig,
+
+    #[serde(default, rename = "secrets")]
+    pub secrets: SecretsConfig,
+
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+
     #[serde(default, rename = "scratch-push")]
     pub push: PushConfig,
+
+    #[serde(default, rename = "scratch-pull")]
+    pub pull: PullConfig,
+
+    #[serde(default, rename = "scratch-ls")]
+    pub list: ListConfig,
+
+    #[serde(default, rename = "scratch-watch")]
+    pub watch: WatchConfig,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
 }
 
 #[derive(Deserialize, Default)]
 pub struct ResponseConfig {
     #[serde(default)]
     pub format: Option<ResponseFormat>,
+
+    #[serde(default)]
+    pub progress: Option<Progress>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// Commands run before and after pushes and pulls
+#[derive(Deserialize, Default)]
+pub struct HooksConfig {
+    #[serde(rename = "pre_push")]
+    pub pre_push: Option<String>,
+
+    #[serde(rename = "post_push")]
+    pub post_push: Option<String>,
+
+    #[serde(rename = "pre_pull")]
+    pub pre_pull: Option<String>,
+
+    #[serde(rename = "post_pull")]
+    pub post_pull: Option<String>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// Rules for spotting secrets in public pushes
+#[derive(Deserialize, Default)]
+pub struct SecretsConfig {
+    #[serde(rename = "disabled", default)]
+    pub disabled: Vec<String>,
+
+    #[serde(rename = "rules", default)]
+    pub rules: BTreeMap<String, Pattern>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// Another account or endpoint, for copying files between them
+#[derive(Deserialize, Default)]
+pub struct ProfileConfig {
+    #[serde(rename = "endpoint")]
+    pub endpoint: Option<String>,
+
+    #[serde(rename = "api_key")]
+    pub api_key: Option<String>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct NetworkConfig {
+    #[serde(rename = "limit_rate", default)]
+    pub limit_rate: Option<Size>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
 }
 
 #[derive(Deserialize, Default)]
@@ -91,6 +176,133 @@ pub struct PushConfig {
 
     #[serde(rename = "private")]
     pub private: Option<bool>,
+
+    #[serde(rename = "pw")]
+    pub pw: Option<String>,
+
+    #[serde(rename = "pw_file")]
+    pub pw_file: Option<String>,
+
+    #[serde(rename = "pw_env")]
+    pub pw_env: Option<String>,
+
+    #[serde(rename = "pw_prompt")]
+    pub pw_prompt: Option<bool>,
+
+    #[serde(rename = "gen_pw")]
+    pub gen_pw: Option<bool>,
+
+    #[serde(rename = "pw_token")]
+    pub pw_token: Option<bool>,
+
+    #[serde(rename = "url")]
+    pub url: Option<bool>,
+
+    #[serde(rename = "share")]
+    pub share: Option<bool>,
+
+    #[serde(rename = "encrypt")]
+    pub encrypt: Option<bool>,
+
+    #[serde(rename = "emit", default)]
+    pub emit: Option<Emit>,
+
+    #[serde(rename = "qr")]
+    pub qr: Option<bool>,
+
+    #[serde(rename = "copy_id")]
+    pub copy_id: Option<bool>,
+
+    #[serde(rename = "atomic")]
+    pub atomic: Option<bool>,
+
+    #[serde(rename = "split", default)]
+    pub split: Option<Size>,
+
+    #[serde(rename = "allow_secrets")]
+    pub allow_secrets: Option<bool>,
+
+    #[serde(rename = "content_type", default)]
+    pub content_type: Option<ContentType>,
+
+    #[serde(rename = "file")]
+    pub file: Option<String>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct PullConfig {
+    #[serde(rename = "anon")]
+    pub anon: Option<bool>,
+
+    #[serde(rename = "pw")]
+    pub pw: Option<String>,
+
+    #[serde(rename = "pw_file")]
+    pub pw_file: Option<String>,
+
+    #[serde(rename = "pw_env")]
+    pub pw_env: Option<String>,
+
+    #[serde(rename = "pw_prompt")]
+    pub pw_prompt: Option<bool>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ListConfig {
+    #[serde(rename = "format", default)]
+    pub format: Option<ResponseFormat>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct WatchConfig {
+    #[serde(rename = "prefix", default)]
+    pub prefix: Option<Prefix>,
+
+    #[serde(rename = "dir")]
+    pub dir: Option<String>,
+
+    #[serde(rename = "delete")]
+    pub delete: Option<bool>,
+
+    #[serde(rename = "existing")]
+    pub existing: Option<bool>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+impl ConfigFile {
+    /// Dotted names of every key that isn't part of the schema, eg. "scratch-push.liftime"
+    pub fn unknown_keys(&self) -> Vec<String> {
+        fn qualify(section: &str, unknown: &BTreeMap<String, toml::Value>) -> Vec<String> {
+            unknown
+                .keys()
+                .map(|key| format!("{}.{}", section, key))
+                .collect()
+        }
+        let mut keys: Vec<String> = self.unknown.keys().cloned().collect();
+        keys.extend(qualify("response", &self.response.unknown));
+        keys.extend(qualify("network", &self.network.unknown));
+        keys.extend(qualify("hooks", &self.hooks.unknown));
+        keys.extend(qualify("secrets", &self.secrets.unknown));
+        for (name, profile) in &self.profiles {
+            keys.extend(qualify(&format!("profile.{}", name), &profile.unknown));
+        }
+        keys.extend(qualify("scratch-push", &self.push.unknown));
+        keys.extend(qualify("scratch-pull", &self.pull.unknown));
+        keys.extend(qualify("scratch-ls", &self.list.unknown));
+        keys.extend(qualify("scratch-watch", &self.watch.unknown));
+        keys
+    }
 }
 
 impl<'de> Deserialize<'de> for Lifetime {
@@ -103,6 +315,26 @@ impl<'de> Deserialize<'de> for Lifetime {
     }
 }
 
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
 impl<'de> Deserialize<'de> for Prefix {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -113,6 +345,26 @@ impl<'de> Deserialize<'de> for Prefix {
     }
 }
 
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Progress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
 impl<'de> Deserialize<'de> for ResponseFormat {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -122,3 +374,13 @@ impl<'de> Deserialize<'de> for ResponseFormat {
         FromStr::from_str(&s).map_err(de::Error::custom)
     }
 }
+
+impl<'de> Deserialize<'de> for Emit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(de::Error::custom)
+    }
+}
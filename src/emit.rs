@@ -0,0 +1,114 @@
+use core::str::FromStr;
+
+use super::api;
+use super::util;
+
+/// A download command for machines that don't have scratch installed
+pub enum Emit {
+    Curl,
+    Wget,
+    Powershell,
+}
+
+impl FromStr for Emit {
+    type Err = util::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "curl" => Ok(Emit::Curl),
+            "wget" => Ok(Emit::Wget),
+            "powershell" => Ok(Emit::Powershell),
+            _ => Err(util::Error::MalformedArgument(
+                "emit",
+                s.into(),
+                "one of curl, wget, powershell".into(),
+            )),
+        }
+    }
+}
+
+/// Everything the download command needs to fetch one file
+pub struct Download<'a> {
+    pub url: &'a str,
+    pub api_key: Option<&'a str>,
+    pub pw: Option<&'a str>,
+    pub output: &'a str,
+}
+
+impl Download<'_> {
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(api_key) = self.api_key {
+            headers.push(("Authorization", format!("Bearer {}", api_key)));
+        }
+        if let Some(pw) = self.pw {
+            headers.push((api::PASSWORD_HEADER, pw.into()));
+        }
+        headers
+    }
+}
+
+/// Quote a word for sh, bash and zsh
+fn sh_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// Quote a word for powershell, where '' is a literal quote
+fn ps_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "''"))
+}
+
+/// Render a command that downloads the file to its output path, readable
+/// only by the current user.  The sh variants set a umask before the file
+/// is created; powershell resets the file's ACL after the download.
+pub fn render(emit: &Emit, download: &Download) -> String {
+    match emit {
+        Emit::Curl => {
+            let mut cmd = String::from("(umask 077 && curl -fsS");
+            for (name, value) in download.headers() {
+                cmd.push_str(&format!(
+                    " -H {}",
+                    sh_quote(&format!("{}: {}", name, value))
+                ));
+            }
+            cmd.push_str(&format!(
+                " -o {} {})",
+                sh_quote(download.output),
+                sh_quote(download.url)
+            ));
+            cmd
+        }
+        Emit::Wget => {
+            let mut cmd = String::from("(umask 077 && wget -q");
+            for (name, value) in download.headers() {
+                cmd.push_str(&format!(
+                    " --header {}",
+                    sh_quote(&format!("{}: {}", name, value))
+                ));
+            }
+            cmd.push_str(&format!(
+                " -O {} {})",
+                sh_quote(download.output),
+                sh_quote(download.url)
+            ));
+            cmd
+        }
+        Emit::Powershell => {
+            let mut cmd = format!("Invoke-WebRequest -Uri {}", ps_quote(download.url));
+            let headers: Vec<String> = download
+                .headers()
+                .into_iter()
+                .map(|(name, value)| format!("{}={}", ps_quote(name), ps_quote(&value)))
+                .collect();
+            if !headers.is_empty() {
+                cmd.push_str(&format!(" -Headers @{{{}}}", headers.join("; ")));
+            }
+            let output = ps_quote(download.output);
+            cmd.push_str(&format!(
+                " -OutFile {0}; if ($?) {{ icacls {0} /inheritance:r /grant:r \"${{env:USERNAME}}:(F)\" | Out-Null }}",
+                output
+            ));
+            cmd
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::Write;
+
+use super::spec::{self, Command, Flag};
+
+const WIDTH: usize = 76;
+const INDENT: usize = 4;
+const COLUMN: usize = 28;
+const COMMAND_COLUMN: usize = 16;
+
+/// Help text for a command, or the top level help when command is None
+pub fn render(command: Option<&Command>) -> String {
+    match command {
+        Some(command) => command_help(command),
+        None => global_help(),
+    }
+}
+
+/// The usage section of the README, which mirrors `scratch help`
+#[cfg(test)]
+fn readme_usage() -> String {
+    format!("```\n$ scratch help\n{}```\n", global_help())
+}
+
+fn usage(command: &Command) -> String {
+    let mut usage = format!("scratch {}", command.name);
+    if !command.flags.is_empty() {
+        usage.push_str(" [OPTIONS]");
+    }
+    for positional in command.positionals {
+        match positional.required {
+            true => {
+                let _ = write!(usage, " {}", positional.name);
+            }
+            false => {
+                let _ = write!(usage, " [{}]", positional.name);
+            }
+        }
+        if positional.repeated {
+            usage.push_str("...");
+        }
+    }
+    usage
+}
+
+fn flag_name(flag: &Flag) -> String {
+    let mut name = match flag.short {
+        Some(short) => format!("-{}, --{}", short, flag.long),
+        None => format!("--{}", flag.long),
+    };
+    match (flag.value, flag.value_optional) {
+        (Some(value), true) => {
+            let _ = write!(name, "[={}]", value);
+        }
+        (Some(value), false) => {
+            let _ = write!(name, " {}", value);
+        }
+        (None, _) => {}
+    }
+    name
+}
+
+/// Split text into lines no wider than width.  Sentences stay separated by
+/// two spaces, the same as the source text.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for sentence in text.split("  ") {
+        for (i, word) in sentence.split_whitespace().enumerate() {
+            let sep = match (line.is_empty(), i) {
+                (true, _) => "",
+                (false, 0) => "  ",
+                (false, _) => " ",
+            };
+            if !line.is_empty() && line.len() + sep.len() + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+                line.push_str(word);
+            } else {
+                line.push_str(sep);
+                line.push_str(word);
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A two column entry, with the help text wrapped in the right column
+fn entry(out: &mut String, column: usize, name: &str, help: &str) {
+    let help = wrap(help, WIDTH - column);
+    let left = format!("{}{}", " ".repeat(INDENT), name);
+    let mut lines = help.iter();
+    if left.len() < column {
+        let first = lines.next().map(String::as_str).unwrap_or("");
+        let _ = writeln!(out, "{:width$}{}", left, first, width = column);
+    } else {
+        let _ = writeln!(out, "{}", left);
+    }
+    for line in lines {
+        let _ = writeln!(out, "{}{}", " ".repeat(column), line);
+    }
+}
+
+fn global_help() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out);
+    let _ = writeln!(out, "USAGE: scratch [OPTIONS] [COMMAND]");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", spec::ABOUT);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "OPTIONS:");
+    for flag in spec::GLOBAL_FLAGS {
+        entry(&mut out, COLUMN, &flag_name(flag), flag.help);
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "COMMAND:");
+    for command in spec::COMMANDS {
+        entry(&mut out, COMMAND_COLUMN, command.name, command.summary);
+    }
+    out
+}
+
+fn command_help(command: &Command) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out);
+    let _ = writeln!(out, "USAGE: {}", usage(command));
+    let _ = writeln!(out);
+    for line in wrap(command.description, WIDTH) {
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = writeln!(out, "(see scratch --help for global options)");
+    if !command.positionals.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "ARGUMENTS:");
+        for positional in command.positionals {
+            entry(&mut out, COMMAND_COLUMN, positional.name, positional.help);
+        }
+    }
+    if !command.flags.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "OPTIONS:");
+        for flag in command.flags {
+            entry(&mut out, COLUMN, &flag_name(flag), flag.help);
+        }
+    }
+    if !command.examples.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "EXAMPLES:");
+        for example in command.examples {
+            let _ = writeln!(out, "{}{}", " ".repeat(INDENT), example);
+        }
+    }
+    out
+}
+
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    match escaped.starts_with('.') || escaped.starts_with('\'') {
+        true => format!("\\&{}", escaped),
+        false => escaped,
+    }
+}
+
+fn roff_flag(out: &mut String, flag: &Flag) {
+    let _ = writeln!(out, ".TP");
+    let mut name = format!("\\fB\\-\\-{}\\fR", roff_escape(flag.long));
+    if let Some(short) = flag.short {
+        name = format!("\\fB\\-{}\\fR, {}", short, name);
+    }
+    match (flag.value, flag.value_optional) {
+        (Some(value), true) => {
+            let _ = write!(name, "[=\\fI{}\\fR]", value);
+        }
+        (Some(value), false) => {
+            let _ = write!(name, " \\fI{}\\fR", value);
+        }
+        (None, _) => {}
+    }
+    if flag.negatable {
+        let _ = write!(name, ", \\fB\\-\\-no\\-{}\\fR", roff_escape(flag.long));
+    }
+    let _ = writeln!(out, "{}", name);
+    let _ = writeln!(out, "{}", roff_escape(flag.help));
+}
+
+/// The scratch(1) man page, checked in at doc/scratch.1 and printed by the
+/// hidden `scratch completions man`
+pub fn man_page() -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        ".TH SCRATCH 1 \"\" \"scratch {}\" \"User Commands\"",
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(out, ".SH NAME");
+    let _ = writeln!(out, "scratch \\- {}", roff_escape(spec::ABOUT));
+    let _ = writeln!(out, ".SH SYNOPSIS");
+    let _ = writeln!(out, ".B scratch");
+    let _ = writeln!(out, "[\\fIOPTIONS\\fR] \\fICOMMAND\\fR");
+    let _ = writeln!(out, ".SH OPTIONS");
+    for flag in spec::GLOBAL_FLAGS {
+        roff_flag(&mut out, flag);
+    }
+    let _ = writeln!(out, ".SH COMMANDS");
+    for command in spec::COMMANDS {
+        let _ = writeln!(out, ".SS \"{}\"", roff_escape(&usage(command)));
+        let _ = writeln!(out, "{}", roff_escape(command.description));
+        for positional in command.positionals {
+            let _ = writeln!(out, ".TP");
+            let _ = writeln!(out, "\\fI{}\\fR", positional.name);
+            let _ = writeln!(out, "{}", roff_escape(positional.help));
+        }
+        for flag in command.flags {
+            roff_flag(&mut out, flag);
+        }
+        if !command.examples.is_empty() {
+            let _ = writeln!(out, ".PP");
+            let _ = writeln!(out, "Examples:");
+            let _ = writeln!(out, ".nf");
+            for example in command.examples {
+                let _ = writeln!(out, ".RS 4");
+                let _ = writeln!(out, "{}", roff_escape(example));
+                let _ = writeln!(out, ".RE");
+            }
+            let _ = writeln!(out, ".fi");
+        }
+    }
+    let _ = writeln!(out, ".SH FILES");
+    let _ = writeln!(out, ".TP");
+    let _ = writeln!(out, "\\fI~/.kilobytetools/config.toml\\fR");
+    let _ = writeln!(
+        out,
+        "Default values for options.  Flags passed on the command line take precedence."
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    const README_START: &str = "<!-- usage:start -->\n";
+    const README_END: &str = "<!-- usage:end -->";
+
+    /// Compare a generated file against the checked in copy.  Set
+    /// SCRATCH_UPDATE_DOCS=1 to rewrite the checked in copy instead.
+    fn check_generated(path: &str, expected: String) {
+        let path = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path);
+        if env::var_os("SCRATCH_UPDATE_DOCS").is_some() {
+            fs::write(&path, &expected).unwrap();
+        }
+        let actual = fs::read_to_string(&path).unwrap();
+        assert!(
+            actual == expected,
+            "{} is out of date, rerun tests with SCRATCH_UPDATE_DOCS=1",
+            path
+        );
+    }
+
+    #[test]
+    fn man_page_is_current() {
+        check_generated("doc/scratch.1", man_page());
+    }
+
+    #[test]
+    fn readme_usage_is_current() {
+        let path = format!("{}/README.md", env!("CARGO_MANIFEST_DIR"));
+        let readme = fs::read_to_string(path).unwrap();
+        let start = readme.find(README_START).unwrap() + README_START.len();
+        let end = readme.find(README_END).unwrap();
+        let expected = format!("{}{}{}", &readme[..start], readme_usage(), &readme[end..]);
+        check_generated("README.md", expected);
+    }
+}
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    fs, io, process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::config_file as cf;
+use super::util;
+
+pub const DEFAULT_HISTORY_PATH: &str = "~/.kilobytetools/history.toml";
+
+/// Pushes without a lifetime get the dataplane's default, which the cli
+/// doesn't know, so they're kept this long
+const UNKNOWN_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// One push, as recorded in the history file
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    pub id: String,
+    pub endpoint: String,
+    /// Unix time of the push, in seconds
+    pub pushed: u64,
+    /// Unix time the file expires, in seconds
+    pub expires: u64,
+    pub size: u64,
+    pub sha256: String,
+    pub lifetime: Option<String>,
+    pub burn: Option<bool>,
+    pub private: Option<bool>,
+    /// The pushed file's path, or stdin or clipboard
+    pub source: String,
+    /// The name and type the file was pushed with
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct History {
+    #[serde(default, rename = "push")]
+    entries: Vec<Entry>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// When a file pushed now with the given lifetime expires
+pub fn expiry(lifetime: Option<&util::Lifetime>) -> u64 {
+    let lifetime = lifetime.map(|l| l.duration()).unwrap_or(UNKNOWN_LIFETIME);
+    // toml integers are signed, so a later time couldn't be saved
+    now()
+        .saturating_add(lifetime.as_secs())
+        .min(i64::MAX as u64)
+}
+
+fn load(path: &str) -> Result<Vec<Entry>, io::Error> {
+    let text = match fs::read_to_string(cf::expand_tilde(path)) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let history: History = toml::from_str(&text).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("malformed history file at {}: {}", path, err),
+        )
+    })?;
+    Ok(history.entries)
+}
+
+/// Rewrite the history file, dropping entries for expired files.  The new
+/// file is written next to it and renamed over it, so a reader never sees a
+/// partial file.
+fn save(path: &str, mut entries: Vec<Entry>) -> Result<Vec<Entry>, io::Error> {
+    let now = now();
+    entries.retain(|entry| entry.expires > now);
+    let history = History { entries };
+    let text = toml::to_string(&history).map_err(io::Error::other)?;
+    let partial = format!("{}.{}.tmp", path, process::id());
+    cf::write(&partial, text)?;
+    if let Err(err) = fs::rename(cf::expand_tilde(&partial), cf::expand_tilde(path)) {
+        let _ = fs::remove_file(cf::expand_tilde(&partial));
+        return Err(err);
+    }
+    Ok(history.entries)
+}
+
+/// Run f while holding a lock on the history file, so scratch processes
+/// running at the same time don't lose each other's changes
+fn locked<T, F>(path: &str, f: F) -> Result<T, io::Error>
+where
+    F: FnOnce() -> Result<T, io::Error>,
+{
+    let lock_path = cf::expand_tilde(&format!("{}.lock", path));
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    lock.lock()?;
+    // the lock is released when the file is dropped
+    f()
+}
+
+pub fn record(entry: Entry) -> Result<(), io::Error> {
+    record_in(DEFAULT_HISTORY_PATH, entry)
+}
+
+fn record_in(path: &str, entry: Entry) -> Result<(), io::Error> {
+    locked(path, || {
+        let mut entries = load(path)?;
+        entries.push(entry);
+        save(path, entries).map(|_| ())
+    })
+}
+
+/// Every file that hasn't expired yet, newest first
+pub fn list() -> Result<Vec<Entry>, io::Error> {
+    list_in(DEFAULT_HISTORY_PATH)
+}
+
+fn list_in(path: &str) -> Result<Vec<Entry>, io::Error> {
+    locked(path, || {
+        let mut entries = load(path)?;
+        let now = now();
+        if entries.iter().any(|entry| entry.expires <= now) {
+            entries = save(path, entries)?;
+        }
+        entries.reverse();
+        Ok(entries)
+    })
+}
+
+/// Drop a file from the history once it's deleted
+pub fn forget(id: &str) -> Result<(), io::Error> {
+    forget_in(DEFAULT_HISTORY_PATH, id)
+}
+
+fn forget_in(path: &str, id: &str) -> Result<(), io::Error> {
+    locked(path, || {
+        let mut entries = load(path)?;
+        let count = entries.len();
+        entries.retain(|entry| entry.id != id);
+        match entries.len() == count {
+            true => Ok(()),
+            false => save(path, entries).map(|_| ()),
+        }
+    })
+}
+
+/// Change the entry for a file whose attributes changed in place
+pub fn update<F>(id: &str, change: F) -> Result<(), io::Error>
+where
+    F: FnOnce(&mut Entry),
+{
+    locked(DEFAULT_HISTORY_PATH, || {
+        let mut entries = load(DEFAULT_HISTORY_PATH)?;
+        match entries.iter_mut().find(|entry| entry.id == id) {
+            Some(entry) => change(entry),
+            None => return Ok(()),
+        }
+        save(DEFAULT_HISTORY_PATH, entries).map(|_| ())
+    })
+}
+
+/// References to the history look like @1 (the newest push), @2, ... or @last
+pub fn is_reference(text: &str) -> bool {
+    text.starts_with('@')
+}
+
+pub fn resolve(reference: &str) -> Result<Entry, io::Error> {
+    resolve_in(DEFAULT_HISTORY_PATH, reference)
+}
+
+fn resolve_in(path: &str, reference: &str) -> Result<Entry, io::Error> {
+    let index = match &reference[1..] {
+        "last" => Some(1),
+        n => n.parse::<usize>().ok().filter(|n| *n > 0),
+    };
+    let index = index.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must look like @1, @2, ... or @last", reference),
+        )
+    })?;
+    let entries = list_in(path)?;
+    let count = entries.len();
+    entries.into_iter().nth(index - 1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "no push {} in history, it has {} unexpired entries",
+                reference, count
+            ),
+        )
+    })
+}
+
+/// A short duration like 5m or 2h, rounded down
+pub fn short_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// One line per entry, numbered the way they're referenced
+pub fn render(entries: &[Entry]) -> String {
+    let now = now();
+    let id_width = entries.iter().map(|e| e.id.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let mut flags = Vec::new();
+        if let Some(true) = entry.burn {
+            flags.push("burn");
+        }
+        match entry.private {
+            Some(true) => flags.push("private"),
+            Some(false) => flags.push("public"),
+            None => {}
+        }
+        let _ = writeln!(
+            out,
+            "{:<4} {:<id_width$}  {:>4} ago  expires in {:>4}  {:>10} B  {:<13}  {}",
+            format!("@{}", i + 1),
+            entry.id,
+            short_duration(now.saturating_sub(entry.pushed)),
+            short_duration(entry.expires.saturating_sub(now)),
+            entry.size,
+            flags.join(","),
+            entry.source,
+            id_width = id_width,
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// A history file of its own for each test, since they run at once
+    fn history_path(test: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("scratch-history-{}", process::id()));
+        let path = dir.join(format!("{}.toml", test));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn entry(id: &str, expires: u64) -> Entry {
+        Entry {
+            id: id.into(),
+            endpoint: "http://localhost".into(),
+            pushed: now(),
+            expires,
+            size: 3,
+            sha256: String::new(),
+            lifetime: None,
+            burn: None,
+            private: None,
+            source: "stdin".into(),
+            file_name: None,
+            content_type: None,
+        }
+    }
+
+    fn ids(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn pushes_are_listed_newest_first_without_expired_ones() {
+        let path = history_path("list");
+        assert!(list_in(&path).unwrap().is_empty());
+        let later = now() + 60;
+        record_in(&path, entry("a", later)).unwrap();
+        record_in(&path, entry("b", later)).unwrap();
+        // expired by the time it's saved, so it never shows up
+        record_in(&path, entry("gone", now() - 1)).unwrap();
+        record_in(&path, entry("c", later)).unwrap();
+        assert_eq!(ids(&list_in(&path).unwrap()), ["c", "b", "a"]);
+        assert_eq!(ids(&load(&path).unwrap()), ["a", "b", "c"]);
+
+        // entries that expire while on disk are pruned when listed
+        let mut entries = load(&path).unwrap();
+        entries[1].expires = now() - 1;
+        fs::write(&path, toml::to_string(&History { entries }).unwrap()).unwrap();
+        assert_eq!(ids(&list_in(&path).unwrap()), ["c", "a"]);
+        assert_eq!(ids(&load(&path).unwrap()), ["a", "c"]);
+    }
+
+    #[test]
+    fn references_count_back_from_the_newest_push() {
+        let path = history_path("resolve");
+        for id in ["a", "b", "c"] {
+            record_in(&path, entry(id, now() + 60)).unwrap();
+        }
+        assert_eq!(resolve_in(&path, "@last").unwrap().id, "c");
+        assert_eq!(resolve_in(&path, "@1").unwrap().id, "c");
+        assert_eq!(resolve_in(&path, "@3").unwrap().id, "a");
+        let kind = |reference: &str| resolve_in(&path, reference).err().unwrap().kind();
+        assert_eq!(kind("@4"), io::ErrorKind::NotFound);
+        for reference in ["@0", "@", "@-1", "@first", "@1.5"] {
+            assert_eq!(
+                kind(reference),
+                io::ErrorKind::InvalidInput,
+                "{}",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn forgotten_files_are_dropped() {
+        let path = history_path("forget");
+        for id in ["a", "b", "c"] {
+            record_in(&path, entry(id, now() + 60)).unwrap();
+        }
+        forget_in(&path, "b").unwrap();
+        forget_in(&path, "unknown").unwrap();
+        assert_eq!(ids(&list_in(&path).unwrap()), ["c", "a"]);
+        assert_eq!(resolve_in(&path, "@2").unwrap().id, "a");
+    }
+
+    #[test]
+    fn concurrent_pushes_are_all_recorded() {
+        let path = history_path("concurrent");
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for n in 0..10 {
+                        let id = format!("{}-{}", writer, n);
+                        record_in(&path, entry(&id, now() + 60)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(list_in(&path).unwrap().len(), 80);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    process::{Command, ExitStatus, Stdio},
+};
+
+/// Commands from the [hooks] section of the config file, run before and
+/// after pushes and pulls
+#[derive(Default)]
+pub struct Hooks {
+    pub pre_push: Option<String>,
+    pub post_push: Option<String>,
+    pub pre_pull: Option<String>,
+    pub post_pull: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    PrePush,
+    PostPush,
+    PrePull,
+    PostPull,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::PrePush => "pre_push",
+            Event::PostPush => "post_push",
+            Event::PrePull => "pre_pull",
+            Event::PostPull => "post_pull",
+        }
+    }
+
+    /// Whether a failing hook stops the push or pull
+    fn rejects(self) -> bool {
+        matches!(self, Event::PrePush | Event::PrePull)
+    }
+}
+
+pub enum ErrorKind {
+    Unrunnable(Event, io::Error),
+    Failed(Event, ExitStatus),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Unrunnable(event, err) => {
+                write!(f, "unable to run the {} hook: {}", event.name(), err)
+            }
+            ErrorKind::Failed(event, status) if event.rejects() => {
+                write!(
+                    f,
+                    "the {} hook rejected the {} ({})",
+                    event.name(),
+                    event.name().trim_start_matches("pre_"),
+                    status
+                )
+            }
+            ErrorKind::Failed(event, status) => {
+                write!(f, "the {} hook failed ({})", event.name(), status)
+            }
+        }
+    }
+}
+
+/// What a hook is told about the file, as SCRATCH_* environment variables.
+/// Values that aren't known are left unset.
+#[derive(Default)]
+pub struct Context<'a> {
+    pub id: Option<&'a str>,
+    pub endpoint: Option<&'a str>,
+    /// The pushed file, or the file a pull was written to
+    pub path: Option<String>,
+    /// Where a push came from: a path, stdin or clipboard
+    pub source: Option<&'a str>,
+    pub size: Option<u64>,
+    pub sha256: Option<&'a str>,
+    pub lifetime: Option<&'a str>,
+    pub prefix: Option<&'a str>,
+    pub burn: Option<bool>,
+    pub private: Option<bool>,
+    /// Passed on the hook's stdin, for inputs that aren't a file
+    pub data: Option<&'a [u8]>,
+}
+
+impl Hooks {
+    pub fn has(&self, event: Event) -> bool {
+        self.command(event).is_some()
+    }
+
+    fn command(&self, event: Event) -> Option<&str> {
+        match event {
+            Event::PrePush => self.pre_push.as_deref(),
+            Event::PostPush => self.post_push.as_deref(),
+            Event::PrePull => self.pre_pull.as_deref(),
+            Event::PostPull => self.post_pull.as_deref(),
+        }
+    }
+
+    /// Run the hook for event, if there is one, through the shell.  Its
+    /// output goes to stderr, so it never mixes with ids or pulled data.
+    pub fn run(&self, event: Event, context: &Context) -> Result<(), ErrorKind> {
+        let command = match self.command(event) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let mut shell = match cfg!(windows) {
+            true => {
+                let mut shell = Command::new("cmd");
+                shell.args(["/C", command]);
+                shell
+            }
+            false => {
+                let mut shell = Command::new("sh");
+                shell.args(["-c", command]);
+                shell
+            }
+        };
+        shell.env("SCRATCH_HOOK", event.name());
+        let yes_no = |b: bool| if b { "true" } else { "false" };
+        let vars = [
+            ("SCRATCH_ID", context.id.map(String::from)),
+            ("SCRATCH_ENDPOINT", context.endpoint.map(String::from)),
+            ("SCRATCH_PATH", context.path.clone()),
+            ("SCRATCH_SOURCE", context.source.map(String::from)),
+            ("SCRATCH_SIZE", context.size.map(|size| size.to_string())),
+            ("SCRATCH_SHA256", context.sha256.map(String::from)),
+            ("SCRATCH_LIFETIME", context.lifetime.map(String::from)),
+            ("SCRATCH_PREFIX", context.prefix.map(String::from)),
+            ("SCRATCH_BURN", context.burn.map(|b| yes_no(b).into())),
+            ("SCRATCH_PRIVATE", context.private.map(|b| yes_no(b).into())),
+        ];
+        for (name, value) in vars {
+            match value {
+                Some(value) => shell.env(name, value),
+                None => shell.env_remove(name),
+            };
+        }
+        let unrunnable = |err| ErrorKind::Unrunnable(event, err);
+        let mut child = shell
+            .stdin(match context.data {
+                Some(_) => Stdio::piped(),
+                None => Stdio::null(),
+            })
+            .stdout(Stdio::from(io::stderr()))
+            .spawn()
+            .map_err(unrunnable)?;
+        if let (Some(data), Some(mut stdin)) = (context.data, child.stdin.take()) {
+            // a hook that decides without reading its input closes the pipe
+            match stdin.write_all(data) {
+                Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(unrunnable(err)),
+                _ => {}
+            }
+        }
+        let status = child.wait().map_err(unrunnable)?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(ErrorKind::Failed(event, status)),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use super::history;
+use super::util;
+
+/// What's known about a file, from the server and the push history.  Fields
+/// that neither knows are None.
+#[derive(Default)]
+pub struct Info {
+    pub id: String,
+    pub exists: bool,
+    pub size: Option<u64>,
+    /// Unix time the file was pushed, in seconds
+    pub pushed: Option<u64>,
+    /// Unix time the file expires, in seconds
+    pub expires: Option<u64>,
+    pub burn: Option<bool>,
+    pub private: Option<bool>,
+    pub password: Option<bool>,
+    pub sha256: Option<String>,
+    /// The pushed file's path, or stdin or clipboard
+    pub source: Option<String>,
+    /// The name a pull with --remote-name writes to
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl Info {
+    /// Fill in what the server didn't say from the history entry of the push
+    pub fn fill_from(&mut self, entry: history::Entry) {
+        self.size = self.size.or(Some(entry.size));
+        self.pushed = Some(entry.pushed);
+        self.expires = self.expires.or(Some(entry.expires));
+        self.burn = self.burn.or(entry.burn);
+        self.private = self.private.or(entry.private);
+        self.sha256 = Some(entry.sha256);
+        self.source = Some(entry.source);
+        self.file_name = self.file_name.take().or(entry.file_name);
+        self.content_type = self.content_type.take().or(entry.content_type);
+    }
+
+    pub fn render(&self, json: bool) -> String {
+        match json {
+            true => self.render_json(),
+            false => self.render_text(),
+        }
+    }
+
+    fn render_text(&self) -> String {
+        let now = history::now();
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        let mut fields = vec![
+            ("id", self.id.clone()),
+            ("exists", yes_no(self.exists).into()),
+        ];
+        if let Some(size) = self.size {
+            fields.push(("size", format!("{} B", size)));
+        }
+        if let Some(pushed) = self.pushed {
+            let ago = history::short_duration(now.saturating_sub(pushed));
+            fields.push(("pushed", format!("{} ago", ago)));
+        }
+        if let Some(expires) = self.expires {
+            let left = history::short_duration(expires.saturating_sub(now));
+            fields.push(("expires", format!("in {}", left)));
+        }
+        for (name, value) in [
+            ("burn", self.burn),
+            ("private", self.private),
+            ("password", self.password),
+        ] {
+            if let Some(value) = value {
+                fields.push((name, yes_no(value).into()));
+            }
+        }
+        if let Some(file_name) = &self.file_name {
+            fields.push(("filename", file_name.clone()));
+        }
+        if let Some(content_type) = &self.content_type {
+            fields.push(("type", content_type.clone()));
+        }
+        if let Some(source) = &self.source {
+            fields.push(("source", source.clone()));
+        }
+        if let Some(sha256) = &self.sha256 {
+            fields.push(("sha256", sha256.clone()));
+        }
+        let mut out = String::new();
+        for (name, value) in fields {
+            let _ = writeln!(out, "{:<9} {}", name, value);
+        }
+        out
+    }
+
+    fn render_json(&self) -> String {
+        let mut fields = vec![
+            format!("\"id\": {}", util::json_string(&self.id)),
+            format!("\"exists\": {}", self.exists),
+        ];
+        for (name, value) in [
+            ("size", self.size),
+            ("pushed", self.pushed),
+            ("expires", self.expires),
+        ] {
+            if let Some(value) = value {
+                fields.push(format!("\"{}\": {}", name, value));
+            }
+        }
+        for (name, value) in [
+            ("burn", self.burn),
+            ("private", self.private),
+            ("password", self.password),
+        ] {
+            if let Some(value) = value {
+                fields.push(format!("\"{}\": {}", name, value));
+            }
+        }
+        for (name, value) in [
+            ("filename", &self.file_name),
+            ("type", &self.content_type),
+            ("source", &self.source),
+            ("sha256", &self.sha256),
+        ] {
+            if let Some(value) = value {
+                fields.push(format!("\"{}\": {}", name, util::json_string(value)));
+            }
+        }
+        format!("{{{}}}\n", fields.join(", "))
+    }
+}
@@ -1,17 +1,37 @@
 mod api;
 mod args;
+mod change;
+mod clipboard;
+mod completions;
 mod config_file;
+mod emit;
+mod help;
+mod history;
+mod hooks;
+mod info;
+mod mime;
+mod progress;
+mod pull;
+mod push;
+mod qr;
+mod secrets;
+mod share;
+mod spec;
+mod split;
+mod throttle;
+mod transfer;
 mod util;
+mod watch;
 
-use api::{BootstrapArgs, ClientOpts, DeleteArgs, ListArgs, PullArgs, PushArgs, StatsArgs};
+use api::{BootstrapArgs, ClientOpts, DeleteArgs, ListArgs, StatsArgs};
 use args::try_get_args;
 use config_file as cf;
-use rpassword;
 use std::{
     fmt::Display,
     io::{self, Write},
     process,
 };
+use util::ResponseFormat;
 
 fn blind<T>(o: Option<T>) -> T {
     o.expect("programming error, please open an issue")
@@ -28,55 +48,68 @@ macro_rules! unwrap_or_exit {
 
 fn main() {
     let args = get_args();
-    let opts = ClientOpts::new(&args.opts.response_format);
+    let opts = ClientOpts::new(
+        &args.opts.response_format,
+        args.opts.progress.unwrap_or_default(),
+        args.opts.limit_rate,
+    );
     let command = blind(args.command);
 
     use args::Command::*;
     match command {
-        Help(msg) => print_help(msg),
+        Help(msg) => print_help(&msg),
         Push(push) => {
-            let endpoint = blind(args.opts.endpoint);
-            let render_prefix = match push.render_url {
-                true => format!("{}/scratch/file/", endpoint),
-                false => "".into(),
-            };
-            let report_id = |id: &String| {
-                println!("{}{}", render_prefix, id.trim());
-                let _ = io::stdout().flush();
-            };
-            let args = PushArgs::new(
+            let json = matches!(
+                args.opts.response_format,
+                Some(ResponseFormat::TextJavascript)
+            );
+            let pushed = push::run(
+                push,
                 blind(args.opts.api_key),
-                endpoint,
-                blind(push.input),
-                push.burn,
-                push.private,
-                push.pw,
-                push.prefix,
-                push.lifetime,
+                blind(args.opts.endpoint),
+                &args.opts.hooks,
+                json,
+                opts,
             );
-            render_response(api::push(args, opts, report_id));
+            match pushed {
+                Ok(()) => {}
+                Err(push::ErrorKind::Api(err)) => render_api_err(err),
+                Err(err) => render_err(err),
+            }
         }
         Pull(pull) => {
-            let args = PullArgs::new(
+            let pulled = pull::run(
+                pull,
                 blind(args.opts.endpoint),
-                pull.id,
                 args.opts.api_key,
-                pull.pw,
-                io::stdout(),
+                &args.opts.hooks,
+                opts,
             );
-            render_response(api::pull(args, opts));
+            match pulled {
+                Ok(()) => {}
+                Err(pull::ErrorKind::Api(err)) => render_api_err(err),
+                Err(err) => render_err(err),
+            }
         }
         List => {
             let args = ListArgs::new(blind(args.opts.api_key), blind(args.opts.endpoint));
             render_response(api::list(args, opts));
         }
         Delete(delete) => {
+            let id = blind(delete.id);
             let args = DeleteArgs::new(
                 blind(args.opts.api_key),
                 blind(args.opts.endpoint),
-                blind(delete.id),
+                id.clone(),
             );
             render_response(api::delete(args, opts));
+            if let Err(err) = history::forget(&id) {
+                eprintln!("unable to update history: {}", err);
+            }
+        }
+        History => {
+            let entries = unwrap_or_exit!(history::list());
+            print!("{}", history::render(&entries));
         }
         Stats => {
             let args = StatsArgs::new(blind(args.opts.api_key), blind(args.opts.endpoint));
@@ -98,6 +131,10 @@ fn main() {
                 lifetime = \"5m\"  # or \"120s\", \"2m\", \"1h\", ...\n\
                 # burn = false\n\
                 # private = true\n\
+                # url = false\n\
+                \n\
+                [scratch-pull]\n\
+                # anon = false\n\
                 "
             );
             match bootstrap.stdout {
@@ -105,6 +142,135 @@ fn main() {
                 false => unwrap_or_exit!(cf::write(cf::DEFAULT_CONFIG_PATH, cfg)),
             }
         }
+        Watch(watch) => {
+            let args = watch::WatchArgs {
+                api_key: blind(args.opts.api_key),
+                endpoint: blind(args.opts.endpoint),
+                prefix: watch.prefix.map(|prefix| prefix.0),
+                dir: watch.dir,
+                delete: watch.delete.unwrap_or(false),
+                existing: watch.existing.unwrap_or(false),
+            };
+            unwrap_or_exit!(watch::run(args, opts));
+        }
+        Info(info) => {
+            let endpoint = blind(args.opts.endpoint);
+            let id = blind(info.id);
+            let info_args = api::InfoArgs::new(blind(args.opts.api_key), endpoint.clone(), id);
+            let mut info = match api::info(info_args, opts) {
+                Ok(info) => info,
+                Err(err) => render_api_err(err),
+            };
+            if info.exists {
+                let pushed = history::list()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|entry| entry.id == info.id && entry.endpoint == endpoint);
+                if let Some(entry) = pushed {
+                    info.fill_from(entry);
+                }
+            }
+            let json = matches!(
+                args.opts.response_format,
+                Some(ResponseFormat::TextJavascript)
+            );
+            print!("{}", info.render(json));
+            if !info.exists {
+                process::exit(EXIT_NO_SUCH_FILE);
+            }
+        }
+        Touch(change) | Chmod(change) => {
+            let id = blind(change.id);
+            let generated_pw = match change.gen_pw {
+                true => change.pw.clone(),
+                false => None,
+            };
+            let new = change::Change {
+                lifetime: change.lifetime,
+                private: change.private,
+                pw: change.pw,
+            };
+            let changed = match change::apply(
+                &id,
+                change.current_pw,
+                new,
+                change.secrets.as_ref(),
+                &blind(args.opts.api_key),
+                &blind(args.opts.endpoint),
+                opts,
+            ) {
+                Ok(changed) => changed,
+                Err(err) => render_api_err(err),
+            };
+            let json = matches!(
+                args.opts.response_format,
+                Some(ResponseFormat::TextJavascript)
+            );
+            if json {
+                let mut fields = vec![format!("\"id\": {}", util::json_string(&changed.id))];
+                if let Some(pw) = &generated_pw {
+                    fields.push(format!("\"pw\": {}", util::json_string(pw)));
+                }
+                if let Some(replaced) = &changed.replaced {
+                    fields.push(format!("\"replaced\": {}", util::json_string(replaced)));
+                }
+                println!("{{{}}}", fields.join(", "));
+            } else {
+                if let Some(replaced) = &changed.replaced {
+                    eprintln!(
+                        "the server can't change {} in place, it was pushed again as {}",
+                        replaced, changed.id
+                    );
+                }
+                println!(
+                    "{}",
+                    push::id_line("", &changed.id, generated_pw.as_deref(), false)
+                );
+            }
+        }
+        Copy(copy) => {
+            let json = matches!(
+                args.opts.response_format,
+                Some(ResponseFormat::TextJavascript)
+            );
+            if let Err(err) = transfer::run(copy, false, json, opts) {
+                render_api_err(err);
+            }
+        }
+        Move(copy) => {
+            let json = matches!(
+                args.opts.response_format,
+                Some(ResponseFormat::TextJavascript)
+            );
+            if let Err(err) = transfer::run(copy, true, json, opts) {
+                render_api_err(err);
+            }
+        }
+        Qr(qr) => {
+            let url = format!(
+                "{}/scratch/file/{}",
+                blind(args.opts.endpoint),
+                blind(qr.id)
+            );
+            print!("{}", unwrap_or_exit!(qr::render(&url)));
+        }
+        Completions(completions) => {
+            let target = blind(completions.target);
+            match completions::script(&target) {
+                Some(script) => print!("{}", script),
+                None => {
+                    // completing ids is best-effort; stay quiet when the
+                    // config file is incomplete or the request fails
+                    if let (Some(api_key), Some(endpoint)) = (args.opts.api_key, args.opts.endpoint)
+                    {
+                        let args = ListArgs::new(api_key, endpoint);
+                        for id in api::list_ids(args).unwrap_or_default() {
+                            println!("{}", id);
+                        }
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -130,12 +296,30 @@ fn print_help(msg: &str) -> ! {
 }
 
 fn render_response(res: Result<String, api::ErrorKind>) {
-    let data = unwrap_or_exit!(res);
+    let data = match res {
+        Ok(data) => data,
+        Err(err) => render_api_err(err),
+    };
     if !data.trim().is_empty() {
         println!("{}", data.trim());
     }
 }
 
+/// Exit status for `pull --wait` giving up, the same one timeout(1) uses
+const EXIT_TIMEOUT: i32 = 124;
+
+/// Exit status for `info` on a file that doesn't exist, set apart from
+/// errors so scripts can tell the two apart
+const EXIT_NO_SUCH_FILE: i32 = 2;
+
+fn render_api_err(err: api::ErrorKind) -> ! {
+    eprintln!("{}", err);
+    match err {
+        api::ErrorKind::Timeout(_) => process::exit(EXIT_TIMEOUT),
+        _ => process::exit(1),
+    }
+}
+
 fn render_err<T: Display>(err: T) -> ! {
     eprintln!("{}", err);
     process::exit(1);
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+/// How much of the data is looked at to tell its type
+pub const SNIFF_LEN: usize = 8192;
+
+const OCTET_STREAM: &str = "application/octet-stream";
+const TEXT: &str = "text/plain; charset=utf-8";
+
+/// The MIME type a file is served with, from the extension of its name, or
+/// failing that from the start of its data
+pub fn detect(file_name: Option<&str>, head: &[u8]) -> &'static str {
+    file_name
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|ext| by_extension(&ext.to_string_lossy().to_ascii_lowercase()))
+        .unwrap_or_else(|| sniff(head))
+}
+
+fn by_extension(ext: &str) -> Option<&'static str> {
+    Some(match ext {
+        "txt" | "log" | "md" | "csv" | "toml" | "ini" | "conf" | "env" | "rs" | "py" | "sh"
+        | "c" | "h" | "go" | "java" | "rb" | "sql" | "diff" | "patch" | "yml" | "yaml" => TEXT,
+        // a browser runs the scripts in these, from the endpoint's origin,
+        // so they're only served as such when --content-type asks for it
+        "html" | "htm" | "xhtml" | "svg" | "xml" => TEXT,
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "bz2" => "application/x-bzip2",
+        "xz" => "application/x-xz",
+        "zst" => "application/zstd",
+        "7z" => "application/x-7z-compressed",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "wasm" => "application/wasm",
+        _ => return None,
+    })
+}
+
+fn sniff(head: &[u8]) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"\x00asm", "application/wasm"),
+    ];
+    if let Some((_, content_type)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return content_type;
+    }
+    // the head may end partway through a character
+    let text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    };
+    match text && !head.contains(&0) {
+        true => TEXT,
+        false => OCTET_STREAM,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_win_over_content() {
+        assert_eq!(detect(Some("logo.PNG"), b"plain text"), "image/png");
+        assert_eq!(detect(Some("dir.d/notes.md"), b"\x89PNG\r\n\x1a\n"), TEXT);
+        assert_eq!(detect(Some("data.json"), b""), "application/json");
+    }
+
+    #[test]
+    fn pages_and_images_with_scripts_are_served_as_text() {
+        for name in [
+            "index.html",
+            "page.HTM",
+            "doc.xhtml",
+            "logo.svg",
+            "feed.xml",
+        ] {
+            assert_eq!(
+                detect(Some(name), b"<script>alert(1)</script>"),
+                TEXT,
+                "{}",
+                name
+            );
+        }
+        assert_eq!(detect(None, b"<!DOCTYPE html><script>"), TEXT);
+        assert_eq!(detect(None, b"<svg onload=alert(1)>"), TEXT);
+    }
+
+    #[test]
+    fn unknown_extensions_sniff_the_content() {
+        assert_eq!(
+            detect(Some("blob.bin"), b"\x89PNG\r\n\x1a\n...."),
+            "image/png"
+        );
+        assert_eq!(detect(Some("Makefile"), b"all:\n\tcc main.c\n"), TEXT);
+        assert_eq!(detect(None, b"%PDF-1.7"), "application/pdf");
+        assert_eq!(detect(None, b"\x1f\x8b\x08"), "application/gzip");
+        assert_eq!(detect(None, b"\x00asm\x01"), "application/wasm");
+    }
+
+    #[test]
+    fn text_is_utf8_without_nul_bytes() {
+        assert_eq!(detect(None, b""), TEXT);
+        assert_eq!(detect(None, "héllo".as_bytes()), TEXT);
+        // cut partway through a character at the end of the head
+        assert_eq!(detect(None, &"héllo".as_bytes()[..2]), TEXT);
+        assert_eq!(detect(None, b"nul\x00inside"), OCTET_STREAM);
+        assert_eq!(detect(None, b"\xff\xfe not utf8"), OCTET_STREAM);
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    time::{Duration, Instant},
+};
+
+use super::util::{self, Progress};
+
+/// Transfers that finish sooner than this never draw a bar
+const BAR_DELAY: Duration = Duration::from_millis(250);
+const BAR_INTERVAL: Duration = Duration::from_millis(100);
+const JSON_INTERVAL: Duration = Duration::from_millis(500);
+const BAR_WIDTH: usize = 24;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Off,
+    Bar,
+    Json,
+}
+
+/// Tracks one upload or download and reports it on stderr
+pub struct Meter {
+    op: &'static str,
+    id: String,
+    total: Option<u64>,
+    done: u64,
+    style: Style,
+    start: Instant,
+    last_report: Option<Instant>,
+}
+
+impl Meter {
+    pub fn new(mode: Progress, op: &'static str, id: &str, total: Option<u64>) -> Self {
+        // a pull to the terminal would draw the bar over the file
+        let shows_data = op == "pull" && io::stdout().is_terminal();
+        let style = match mode {
+            Progress::Auto if io::stderr().is_terminal() && !shows_data => Style::Bar,
+            Progress::Always => Style::Bar,
+            Progress::Json => Style::Json,
+            _ => Style::Off,
+        };
+        Self {
+            op,
+            id: id.into(),
+            total,
+            done: 0,
+            style,
+            start: Instant::now(),
+            last_report: None,
+        }
+    }
+
+    fn add(&mut self, bytes: usize) {
+        self.done += bytes as u64;
+        let now = Instant::now();
+        let due = match (self.style, self.last_report) {
+            (Style::Off, _) => false,
+            (Style::Bar, None) => now - self.start >= BAR_DELAY,
+            (Style::Bar, Some(last)) => now - last >= BAR_INTERVAL,
+            (Style::Json, None) => now - self.start >= JSON_INTERVAL,
+            (Style::Json, Some(last)) => now - last >= JSON_INTERVAL,
+        };
+        if due {
+            self.last_report = Some(now);
+            self.report(false);
+        }
+    }
+
+    /// Report the end of the transfer, successful or not
+    pub fn finish(&mut self, ok: bool) {
+        match self.style {
+            Style::Off => {}
+            // a bar is only finished if it was started
+            Style::Bar if self.last_report.is_none() => {}
+            Style::Bar => {
+                if ok {
+                    self.report(true);
+                }
+                eprintln!();
+            }
+            Style::Json => self.report(ok),
+        }
+        self.style = Style::Off;
+    }
+
+    /// Bytes per second since the transfer started
+    fn rate(&self) -> u64 {
+        let secs = self.start.elapsed().as_secs_f64();
+        match secs > 0.0 {
+            true => (self.done as f64 / secs) as u64,
+            false => 0,
+        }
+    }
+
+    fn eta(&self) -> Option<u64> {
+        let (total, rate) = (self.total?, self.rate());
+        match rate {
+            0 => None,
+            rate => Some(total.saturating_sub(self.done) / rate),
+        }
+    }
+
+    fn report(&self, done: bool) {
+        let line = match self.style {
+            Style::Off => return,
+            Style::Bar => format!("\r\x1b[K{}", self.bar()),
+            Style::Json => format!("{}\n", self.json(done)),
+        };
+        let mut stderr = io::stderr();
+        let _ = stderr.write_all(line.as_bytes());
+        let _ = stderr.flush();
+    }
+
+    fn bar(&self) -> String {
+        let mut line = format!("{} {}  ", self.op, self.id);
+        if let Some(total) = self.total.filter(|total| *total > 0) {
+            let fraction = (self.done as f64 / total as f64).min(1.0);
+            let filled = (fraction * BAR_WIDTH as f64) as usize;
+            line.push_str(&format!(
+                "[{}{}] {:>3}%  {} / {}",
+                "#".repeat(filled),
+                " ".repeat(BAR_WIDTH - filled),
+                (fraction * 100.0) as u64,
+                human_size(self.done),
+                human_size(total),
+            ));
+        } else {
+            line.push_str(&human_size(self.done));
+        }
+        line.push_str(&format!("  {}/s", human_size(self.rate())));
+        if let Some(eta) = self.eta() {
+            line.push_str(&format!("  ETA {}:{:02}", eta / 60, eta % 60));
+        }
+        line
+    }
+
+    fn json(&self, done: bool) -> String {
+        let optional = |n: Option<u64>| n.map_or("null".into(), |n| n.to_string());
+        format!(
+            "{{\"op\": \"{}\", \"id\": {}, \"bytes\": {}, \"total\": {}, \"rate\": {}, \"eta\": {}, \"done\": {}}}",
+            self.op,
+            util::json_string(&self.id),
+            self.done,
+            optional(self.total),
+            self.rate(),
+            optional(self.eta()),
+            done,
+        )
+    }
+}
+
+/// Sizes like 12.3 MiB
+fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Counts the bytes read through it
+pub struct Reader<'a, R> {
+    pub inner: R,
+    pub meter: &'a mut Meter,
+}
+
+impl<R: Read> Read for Reader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.meter.add(n);
+        Ok(n)
+    }
+}
+
+/// Counts the bytes written through it
+pub struct Writer<'a, W> {
+    pub inner: W,
+    pub meter: &'a mut Meter,
+}
+
+impl<W: Write> Write for Writer<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.meter.add(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
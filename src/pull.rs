@@ -0,0 +1,299 @@
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use super::api::{self, ClientOpts, PullArgs};
+use super::args;
+use super::clipboard;
+use super::history;
+use super::hooks::{self, Hooks};
+use super::share;
+use super::split;
+use super::util;
+use super::watch;
+
+pub enum ErrorKind {
+    Api(api::ErrorKind),
+    Io(io::Error),
+    Hook(hooks::ErrorKind),
+    /// The pull can't be done the way it was asked for
+    Refused(String),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Api(err) => write!(f, "{}", err),
+            ErrorKind::Io(err) => write!(f, "{}", err),
+            ErrorKind::Hook(err) => write!(f, "{}", err),
+            ErrorKind::Refused(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<api::ErrorKind> for ErrorKind {
+    fn from(err: api::ErrorKind) -> Self {
+        ErrorKind::Api(err)
+    }
+}
+
+impl From<io::Error> for ErrorKind {
+    fn from(err: io::Error) -> Self {
+        ErrorKind::Io(err)
+    }
+}
+
+impl From<hooks::ErrorKind> for ErrorKind {
+    fn from(err: hooks::ErrorKind) -> Self {
+        ErrorKind::Hook(err)
+    }
+}
+
+/// Pull a file to stdout, a file or the clipboard, joining the parts of a
+/// split file and decrypting a shared one, with the pull hooks around it
+pub fn run(
+    mut pull: args::PullArgs,
+    endpoint: String,
+    api_key: Option<String>,
+    hooks: &Hooks,
+    opts: ClientOpts,
+) -> Result<(), ErrorKind> {
+    if pull.remote_name {
+        let (id, path) = remote_path(&pull, &endpoint, api_key.clone(), opts)?;
+        // pull the file that was named, even if a newer one turns up
+        pull.id = Some(id);
+        pull.out = Some(path);
+    }
+    let hook_path = pull.out.as_ref().map(|path| path.display().to_string());
+    let requested_id = pull.id.clone();
+    let context = hooks::Context {
+        id: requested_id.as_deref(),
+        endpoint: Some(&endpoint),
+        path: hook_path.clone(),
+        ..hooks::Context::default()
+    };
+    hooks.run(hooks::Event::PrePull, &context)?;
+    // --out replaces a file, but a name from the server only with --clobber
+    let keep_existing = pull.remote_name && !pull.clobber;
+    let (pulled_id, size) = match (pull.key.take(), pull.to_clipboard) {
+        (None, false) => pull_streamed(pull, &endpoint, api_key, keep_existing, opts)?,
+        (key, to_clipboard) => {
+            // encrypted shares are buffered so they can be authenticated
+            // before anything is written
+            let mut buf = Vec::new();
+            let pull_args = PullArgs::new(
+                endpoint.clone(),
+                pull.id,
+                api_key.clone(),
+                pull.pw.clone(),
+                pull.wait,
+                &mut buf,
+            )
+            .range(pull.range);
+            let (pulled_id, content_type) = api::pull(pull_args, opts)?;
+            if split::is_manifest(&buf)
+                && split::is_marked(&pulled_id, content_type.as_deref())
+                && pull.range.is_none()
+            {
+                let manifest = split::Manifest::parse(&buf)?;
+                let mut joined = Vec::new();
+                split::pull_parts(
+                    &manifest,
+                    &endpoint,
+                    api_key.as_deref(),
+                    pull.pw.as_deref(),
+                    opts,
+                    &mut joined,
+                )?;
+                buf = joined;
+            }
+            let data = match key {
+                Some(key) => share::decrypt(&key, &buf)?,
+                None => buf,
+            };
+            match (to_clipboard, &pull.out) {
+                (true, _) => clipboard::write(&data)?,
+                (false, Some(path)) => create_out(path, keep_existing)?.write_all(&data)?,
+                (false, None) => io::stdout().write_all(&data)?,
+            }
+            (Some(pulled_id), Some(data.len() as u64))
+        }
+    };
+    let context = hooks::Context {
+        id: pulled_id.as_deref(),
+        endpoint: Some(&endpoint),
+        path: hook_path,
+        size,
+        ..hooks::Context::default()
+    };
+    hooks.run(hooks::Event::PostPull, &context)?;
+    Ok(())
+}
+
+/// Pull a file straight to stdout or --out, resuming it with --resume.
+/// Returns the id that was pulled and the size of the file written.
+fn pull_streamed(
+    mut pull: args::PullArgs,
+    endpoint: &str,
+    api_key: Option<String>,
+    keep_existing: bool,
+    opts: ClientOpts,
+) -> Result<(Option<String>, Option<u64>), ErrorKind> {
+    let mut range = pull.range;
+    let mut resumed = None;
+    let mut output: Box<dyn Write> = match &pull.out {
+        Some(path) if pull.resume => {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            let local = file.metadata()?.len();
+            let head = PullArgs::new(
+                endpoint.into(),
+                pull.id.clone(),
+                api_key.clone(),
+                pull.pw.clone(),
+                None,
+                io::sink(),
+            );
+            let remote = api::remote_head(&head, opts)?;
+            if split::is_marked(&remote.id, remote.content_type.as_deref()) {
+                return Err(ErrorKind::Refused(
+                    "--resume can't continue a split file, pull it again without --resume".into(),
+                ));
+            }
+            if let Some(size) = remote.size.filter(|size| *size < local) {
+                return Err(ErrorKind::Refused(format!(
+                    "{} has {} bytes but {} only has {}, so it isn't a partial copy",
+                    path.display(),
+                    local,
+                    remote.id,
+                    size
+                )));
+            }
+            // continue the file that was checked, even if a newer one turns up
+            resumed = Some((local, remote.size == Some(local)));
+            pull.id = Some(remote.id);
+            range = Some(util::Range::from(local));
+            Box::new(file)
+        }
+        Some(path) => Box::new(create_out(path, keep_existing)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut sniff = split::Sniff::new(&mut output);
+    let pull_args = PullArgs::new(
+        endpoint.into(),
+        pull.id.clone(),
+        api_key.clone(),
+        pull.pw.clone(),
+        pull.wait,
+        &mut sniff,
+    )
+    .range(range);
+    let pulled = match resumed {
+        // nothing past the end of the file, it's already complete
+        Some((_, true)) => Ok((pull.id.clone().unwrap_or_default(), None)),
+        _ => api::pull(pull_args, opts),
+    };
+    let (pulled_id, content_type) = match pulled {
+        Ok((id, content_type)) => (Some(id), content_type),
+        // the server didn't give the size up front, but the range it turned
+        // down may show the file is complete
+        Err(api::ErrorKind::RangeNotSatisfiable(Some(size)))
+            if resumed.is_some_and(|(local, _)| local == size) =>
+        {
+            (pull.id.clone(), None)
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let marked = pulled_id
+        .as_deref()
+        .is_some_and(|id| split::is_marked(id, content_type.as_deref()));
+    match sniff.finish(marked)? {
+        // --range and --head are taken from the stored bytes, even when
+        // they're a manifest
+        Some(manifest) if pull.range.is_some() => output.write_all(&manifest)?,
+        Some(manifest) => {
+            let manifest = split::Manifest::parse(&manifest)?;
+            split::pull_parts(
+                &manifest,
+                endpoint,
+                api_key.as_deref(),
+                pull.pw.as_deref(),
+                opts,
+                &mut output,
+            )?;
+        }
+        None => {}
+    }
+    output.flush()?;
+    let size = pull
+        .out
+        .as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len());
+    Ok((pulled_id, size))
+}
+
+/// Open the file a pull writes to, failing if it exists and should be kept
+fn create_out(path: &Path, keep_existing: bool) -> Result<fs::File, io::Error> {
+    match keep_existing {
+        true => fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    err.kind(),
+                    format!(
+                        "{} already exists, pass --clobber to replace it",
+                        path.display()
+                    ),
+                ),
+                _ => err,
+            }),
+        false => fs::File::create(path),
+    }
+}
+
+/// Where `pull --remote-name` writes a file: the name it was pushed with,
+/// from the server or the push history, or else its id.  Returns the id,
+/// with a name resolved to the newest file pushed with it.
+fn remote_path(
+    pull: &args::PullArgs,
+    endpoint: &str,
+    api_key: Option<String>,
+    opts: ClientOpts,
+) -> Result<(String, PathBuf), api::ErrorKind> {
+    let head = PullArgs::new(
+        endpoint.into(),
+        pull.id.clone(),
+        api_key,
+        pull.pw.clone(),
+        None,
+        io::sink(),
+    );
+    let api::Head {
+        id,
+        file_name: served,
+        ..
+    } = api::remote_head(&head, opts)?;
+    let recorded = || {
+        history::list()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|entry| entry.id == id && entry.endpoint == endpoint)
+            .and_then(|entry| entry.file_name)
+    };
+    // only the last component, so a name can't point outside the directory,
+    // and not a hidden one like .bashrc
+    let name = served
+        .or_else(recorded)
+        .and_then(|name| Path::new(&name).file_name().map(PathBuf::from))
+        .filter(|name| !name.to_string_lossy().starts_with('.'))
+        .unwrap_or_else(|| watch::file_name(&id).into());
+    Ok((id, name))
+}
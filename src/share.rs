@@ -0,0 +1,208 @@
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use core::str::FromStr;
+use std::{fmt::Display, io};
+
+use super::util;
+
+const SCHEME: &str = "scratch://";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Everything needed to pull a file on a machine without a config file.
+/// Rendered as scratch:// followed by the urlsafe base64 of each field,
+/// separated by newlines.
+pub struct ShareToken {
+    pub endpoint: String,
+    pub id: String,
+    pub pw: Option<String>,
+    pub key: Option<Vec<u8>>,
+}
+
+impl ShareToken {
+    pub fn is_token(text: &str) -> bool {
+        text.starts_with(SCHEME)
+    }
+}
+
+impl Display for ShareToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = match &self.key {
+            Some(key) => base64::encode_config(key, base64::URL_SAFE_NO_PAD),
+            None => "".into(),
+        };
+        let payload = format!(
+            "{}\n{}\n{}\n{}",
+            self.endpoint,
+            self.id,
+            self.pw.as_deref().unwrap_or(""),
+            key
+        );
+        write!(
+            f,
+            "{}{}",
+            SCHEME,
+            base64::encode_config(payload, base64::URL_SAFE_NO_PAD)
+        )
+    }
+}
+
+impl FromStr for ShareToken {
+    type Err = util::Error;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let malformed = || {
+            util::Error::MalformedArgument(
+                "share token",
+                token.into(),
+                "the output of scratch push --share".into(),
+            )
+        };
+        let encoded = token.trim().strip_prefix(SCHEME).ok_or_else(malformed)?;
+        let payload =
+            base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).map_err(|_| malformed())?;
+        let payload = String::from_utf8(payload).map_err(|_| malformed())?;
+        let fields: Vec<&str> = payload.split('\n').collect();
+        let (endpoint, id, pw, key) = match fields[..] {
+            [endpoint, id, pw, key] if !endpoint.is_empty() && !id.is_empty() => {
+                (endpoint, id, pw, key)
+            }
+            _ => return Err(malformed()),
+        };
+        let key = match key {
+            "" => None,
+            key => {
+                let key =
+                    base64::decode_config(key, base64::URL_SAFE_NO_PAD).map_err(|_| malformed())?;
+                if key.len() != KEY_LEN {
+                    return Err(malformed());
+                }
+                Some(key)
+            }
+        };
+        Ok(Self {
+            endpoint: endpoint.into(),
+            id: id.into(),
+            pw: match pw {
+                "" => None,
+                pw => Some(pw.into()),
+            },
+            key,
+        })
+    }
+}
+
+pub fn generate_key() -> Result<Vec<u8>, io::Error> {
+    let mut key = vec![0u8; KEY_LEN];
+    getrandom::getrandom(&mut key)?;
+    Ok(key)
+}
+
+/// Encrypt data with ChaCha20-Poly1305.  The random nonce is prepended to
+/// the ciphertext.
+pub fn encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut nonce)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|_| io::Error::other("unable to encrypt input"))?;
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unable to decrypt file, it was modified or the share token is wrong",
+        )
+    };
+    if data.len() < NONCE_LEN {
+        return Err(invalid());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_data_round_trips() {
+        let key = generate_key().unwrap();
+        for data in [&b""[..], b"hunter2", &[0u8; 4096][..]] {
+            let sealed = encrypt(&key, data).unwrap();
+            assert_eq!(sealed.len(), NONCE_LEN + data.len() + 16);
+            assert_eq!(decrypt(&key, &sealed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let key = generate_key().unwrap();
+        let sealed = encrypt(&key, b"hunter2").unwrap();
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt(&key, &tampered).is_err(), "flipped byte {}", i);
+        }
+        assert!(decrypt(&key, &sealed[..sealed.len() - 1]).is_err());
+        assert!(decrypt(&key, &sealed[..NONCE_LEN - 1]).is_err());
+        assert!(decrypt(&generate_key().unwrap(), &sealed).is_err());
+    }
+
+    #[test]
+    fn tokens_round_trip() {
+        let token = ShareToken {
+            endpoint: "https://example.com".into(),
+            id: "logs:97a292d7".into(),
+            pw: Some("hunter2".into()),
+            key: Some(vec![7; KEY_LEN]),
+        };
+        let parsed: ShareToken = token.to_string().parse().unwrap();
+        assert_eq!(parsed.endpoint, token.endpoint);
+        assert_eq!(parsed.id, token.id);
+        assert_eq!(parsed.pw, token.pw);
+        assert_eq!(parsed.key, token.key);
+
+        let bare = ShareToken {
+            pw: None,
+            key: None,
+            ..token
+        };
+        let parsed: ShareToken = bare.to_string().parse().unwrap();
+        assert_eq!(parsed.pw, None);
+        assert_eq!(parsed.key, None);
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let encode = |payload: &str| {
+            format!(
+                "{}{}",
+                SCHEME,
+                base64::encode_config(payload, base64::URL_SAFE_NO_PAD)
+            )
+        };
+        let short_key = base64::encode_config([7; KEY_LEN - 1], base64::URL_SAFE_NO_PAD);
+        for token in [
+            "".into(),
+            "97a292d7".into(),
+            format!("{}not base64!", SCHEME),
+            encode("https://example.com\n97a292d7\n"),
+            encode("\n97a292d7\n\n"),
+            encode("https://example.com\n\n\n"),
+            encode(&format!("https://example.com\n97a292d7\n\n{}", short_key)),
+        ] {
+            assert!(token.parse::<ShareToken>().is_err(), "{:?}", token);
+        }
+    }
+}
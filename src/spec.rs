@@ -0,0 +1,754 @@
+//! Declarative description of every command and flag the cli understands.
+//! The argument parser validates flags against these tables, and the help
+//! text, man page and shell completions are generated from them.
+
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    /// The value can be left out, and must be attached with = when given
+    pub value_optional: bool,
+    pub negatable: bool,
+    pub complete: Complete,
+    pub help: &'static str,
+}
+
+pub struct Positional {
+    pub name: &'static str,
+    pub required: bool,
+    /// Whether the argument can be given more than once
+    pub repeated: bool,
+    pub complete: Complete,
+    pub help: &'static str,
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub description: &'static str,
+    pub flags: &'static [Flag],
+    pub positionals: &'static [Positional],
+    pub examples: &'static [&'static str],
+}
+
+#[derive(Clone, Copy)]
+pub enum Complete {
+    Nothing,
+    Path,
+    FileIds,
+    Choices(&'static [&'static str]),
+}
+
+impl Flag {
+    /// A boolean flag, which can be negated with --no-NAME
+    const fn switch(long: &'static str, help: &'static str) -> Self {
+        Self {
+            long,
+            short: None,
+            value: None,
+            value_optional: false,
+            negatable: true,
+            complete: Complete::Nothing,
+            help,
+        }
+    }
+
+    /// A flag that takes a value, eg. --lifetime 5m
+    const fn value(long: &'static str, value: &'static str, help: &'static str) -> Self {
+        Self {
+            long,
+            short: None,
+            value: Some(value),
+            value_optional: false,
+            negatable: false,
+            complete: Complete::Nothing,
+            help,
+        }
+    }
+
+    const fn complete(mut self, complete: Complete) -> Self {
+        self.complete = complete;
+        self
+    }
+
+    /// Allow the value to be left out, eg. --wait or --wait=10m
+    const fn optional(mut self) -> Self {
+        self.value_optional = true;
+        self
+    }
+
+    const fn short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    const fn fixed(mut self) -> Self {
+        self.negatable = false;
+        self
+    }
+
+    /// Every spelling of this flag, including the negated form
+    pub fn spellings(&self) -> Vec<String> {
+        let mut names = vec![format!("--{}", self.long)];
+        if self.negatable {
+            names.push(format!("--no-{}", self.long));
+        }
+        names
+    }
+
+    pub fn accepts(&self, spelling: &str) -> bool {
+        match spelling.strip_prefix("--") {
+            Some(long) => {
+                long == self.long || (self.negatable && long.strip_prefix("no-") == Some(self.long))
+            }
+            None => spelling.strip_prefix('-') == self.short.map(String::from).as_deref(),
+        }
+    }
+}
+
+impl Command {
+    /// Global flags are accepted by every command
+    pub fn accepts(&self, spelling: &str) -> bool {
+        GLOBAL_FLAGS
+            .iter()
+            .chain(self.flags.iter())
+            .any(|f| f.accepts(spelling))
+    }
+
+    pub fn spellings(&self) -> Vec<String> {
+        GLOBAL_FLAGS
+            .iter()
+            .chain(self.flags.iter())
+            .flat_map(|f| f.spellings())
+            .collect()
+    }
+}
+
+pub fn command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+pub fn is_global(spelling: &str) -> bool {
+    GLOBAL_FLAGS.iter().any(|f| f.accepts(spelling))
+}
+
+/// Names of the commands that accept a flag
+pub fn commands_accepting(spelling: &str) -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .filter(|c| c.flags.iter().any(|f| f.accepts(spelling)))
+        .map(|c| c.name)
+        .collect()
+}
+
+/// The closest candidate to a misspelled input, if any is close enough
+pub fn suggest<I, S>(input: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let max_distance = (input.len() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(input, c.as_ref()), c.as_ref().to_string()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+pub const ABOUT: &str = "Easily transmit small bits of short-lived data.";
+
+pub const RESPONSE_FORMATS: &[&str] = &["text/plain", "text/javascript", "txt", "js"];
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+pub const PROGRESS_MODES: &[&str] = &["auto", "always", "never", "json"];
+
+pub const EMITTERS: &[&str] = &["curl", "wget", "powershell"];
+
+pub const GLOBAL_FLAGS: &[Flag] = &[
+    Flag::switch("help", "Print help for the command.")
+        .short('h')
+        .fixed(),
+    Flag::value(
+        "api-key",
+        "API_KEY",
+        "API Key found in your account settings page.",
+    ),
+    Flag::value(
+        "endpoint",
+        "ENDPOINT",
+        "Endpoint for dataplane operations, found in your account settings page.",
+    ),
+    Flag::value(
+        "out-format",
+        "FORMAT",
+        "Control how responses are rendered.  Allowed values [text/plain, text/javascript, txt, js]",
+    )
+    .complete(Complete::Choices(RESPONSE_FORMATS)),
+    Flag::value(
+        "progress",
+        "WHEN",
+        "Report progress of uploads and downloads on stderr.  auto draws a progress bar when stderr is a terminal, json prints one JSON object per line for wrapper scripts.  Allowed values [auto, always, never, json]",
+    )
+    .complete(Complete::Choices(PROGRESS_MODES)),
+    Flag::switch(
+        "no-hooks",
+        "Don't run the pre_push, post_push, pre_pull and post_pull hooks from the config file.",
+    )
+    .fixed(),
+];
+
+/// Attributes cp and mv can change on the copy
+const COPY_FLAGS: &[Flag] = &[
+    Flag::value(
+        "lifetime",
+        "LIFETIME",
+        "The lifetime of the copy, instead of what the original has left.  Format: \\d+(h|m|s)",
+    ),
+    Flag::switch("private", "Whether the copy can be read by anyone."),
+    Flag::switch("burn", "Whether the copy is deleted after it's first pulled."),
+    Flag::value(
+        "pw",
+        "PASSWORD",
+        "A new password for the copy.  Format: [a-zA-Z0-9._-]{1,20}  Note: visible to other users in ps and your shell history, prefer the options below",
+    ),
+    Flag::value(
+        "pw-file",
+        "FILE",
+        "Read the new password from the first line of a file",
+    )
+    .complete(Complete::Path),
+    Flag::value(
+        "pw-env",
+        "VAR",
+        "Read the new password from an environment variable",
+    ),
+    Flag::switch("pw-prompt", "Prompt for the new password").fixed(),
+    Flag::switch(
+        "gen-pw",
+        "Generate a random new password for the copy, and print it next to the id",
+    )
+    .fixed(),
+    Flag::value(
+        "current-pw",
+        "PW",
+        "The password of the original, which the copy keeps unless it gets a new one.  An ID:PASSWORD token works too.",
+    ),
+    Flag::switch(
+        "allow-burn",
+        "Copy the file even if it's a burn file, or might be one, which uses it up",
+    )
+    .fixed(),
+    Flag::switch(
+        "allow-secrets",
+        "Make a copy that isn't private even if the file looks like it contains credentials, such as keys or tokens",
+    ),
+];
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "push",
+        summary: "Upload the contents of a file",
+        description: "Upload a file.  The key of the created file is printed.  When pushing from stdin, buffers the entire input into memory.",
+        flags: &[
+            Flag::switch(
+                "stdin",
+                "(default) Push data from stdin.  Note: buffers input to memory before writing",
+            )
+            .fixed(),
+            Flag::value("file", "FILE", "Push the named file").complete(Complete::Path),
+            Flag::switch(
+                "from-clipboard",
+                "Push the contents of the clipboard, read with wl-paste, xclip or pbpaste",
+            )
+            .fixed(),
+            Flag::value(
+                "lifetime",
+                "LIFETIME",
+                "How long the file should live eg. 10m.  Format: \\d+(h|m|s)",
+            ),
+            Flag::switch("private", "Whether the file can be read by anyone."),
+            Flag::value(
+                "pw",
+                "PASSWORD",
+                "Optional password that will be required to read the file.  Format: [a-zA-Z0-9._-]{1,20}  Note: visible to other users in ps and your shell history, prefer the options below",
+            ),
+            Flag::value(
+                "pw-file",
+                "FILE",
+                "Read the password from the first line of a file",
+            )
+            .complete(Complete::Path),
+            Flag::value(
+                "pw-env",
+                "VAR",
+                "Read the password from an environment variable",
+            ),
+            Flag::switch("pw-prompt", "Prompt for the password").fixed(),
+            Flag::switch(
+                "gen-pw",
+                "Generate a random password with the most entropy the format allows, and print it next to the id",
+            )
+            .fixed(),
+            Flag::switch(
+                "pw-token",
+                "With --gen-pw, print a single ID:PASSWORD token instead, which scratch pull accepts in place of an id",
+            ),
+            Flag::switch(
+                "burn",
+                "Whether the file should be deleted the first time it's read",
+            ),
+            Flag::value(
+                "prefix",
+                "PREFIX",
+                "Optional prefix for the random file key.  Useful for segmenting temporary files by use.  Format: [a-zA-Z0-9._-:|]{1,64}",
+            ),
+            Flag::value(
+                "name",
+                "NAME",
+                "Give the file a stable name that scratch pull --name fetches.  Older files with the same name are deleted once the push succeeds.  Stored as the prefix NAME|, so it can't be combined with --prefix.  Format: [a-zA-Z0-9._-]{1,63}",
+            ),
+            Flag::switch(
+                "share",
+                "Print a share token instead of the id.  The token holds the endpoint, id and password, so scratch pull TOKEN works on a machine without a config file.  Implies --no-private, and --gen-pw when no password is set",
+            ),
+            Flag::switch(
+                "encrypt",
+                "With --share, encrypt the file before uploading it.  The key is only stored in the share token",
+            ),
+            Flag::switch(
+                "url",
+                "Prints out the complete file url, not just the id.  Useful when pushing public data to access without a scratch client.",
+            ),
+            Flag::value(
+                "emit",
+                "TOOL",
+                "Print a command that downloads the file with curl, wget or powershell, for machines without scratch.  The command includes the password.  Private files need your api key to download, so they also need --emit-with-api-key",
+            )
+            .complete(Complete::Choices(EMITTERS)),
+            Flag::switch(
+                "emit-with-api-key",
+                "Let --emit put your api key in the command for a private file.  Anyone who sees the command can use your account",
+            )
+            .fixed(),
+            Flag::switch(
+                "copy-id",
+                "Copy the printed id, url or token to the clipboard.  Uses wl-copy, xclip or pbcopy, and falls back to asking the terminal with OSC 52",
+            ),
+            Flag::switch(
+                "qr",
+                "Also print the file url, or the share token with --share, as a QR code.  Useful for pulling on a phone",
+            ),
+            Flag::value(
+                "split",
+                "SIZE",
+                "Push the input in parts of at most SIZE, for inputs over the per-file limit, then push a manifest listing the parts and print its id.  scratch pull MANIFEST_ID fetches, checks and joins the parts.  Parts get the same options but no prefix, so --prefix and --name only apply to the manifest.  Format: \\d+(k|m|g)",
+            ),
+            Flag::switch(
+                "atomic",
+                "When pushing several files, delete the ones already pushed if any upload fails",
+            ),
+            Flag::value(
+                "content-type",
+                "TYPE",
+                "Serve the raw url with this MIME type, eg. image/png, instead of one detected from the file name or contents.  HTML, SVG and XML are only served as such with this flag, and as plain text otherwise",
+            ),
+            Flag::switch(
+                "allow-secrets",
+                "Push a file that isn't private even if it looks like it contains credentials, such as keys or tokens",
+            ),
+            Flag::value(
+                "limit-rate",
+                "RATE",
+                "Upload at most RATE bytes per second, eg. 500k, so the push doesn't saturate a shared link.  Format: \\d+(k|m|g)",
+            ),
+        ],
+        positionals: &[Positional {
+            name: "FILE",
+            required: false,
+            repeated: true,
+            complete: Complete::Path,
+            help: "Files to push, same as --file.  Several files can be pushed at once with the same options, their ids are printed in order, or as a JSON object of path to id with --out-format js.",
+        }],
+        examples: &[
+            "scratch push --lifetime 2h < ~/.ssh/id_rsa.pub",
+            "scratch push --burn --prefix creds.aws: --file ~/.aws/config",
+            "scratch push --no-private --url <<< \"hello, world\"",
+            "scratch push --gen-pw --pw-token < ~/.aws/credentials",
+            "scratch push --share --encrypt --lifetime 10m < bootstrap/creds.txt",
+            "scratch push --no-private --gen-pw --emit curl --file bootstrap/init.sh",
+            "scratch push --from-clipboard --copy-id",
+            "scratch push --name deploy-config --file config/deploy.toml",
+            "scratch push --atomic --lifetime 1h build/*.tar.gz",
+            "scratch push --split 50m --file backups/db.sql.gz",
+        ],
+    },
+    Command {
+        name: "pull",
+        summary: "Get the contents of a file",
+        description: "Pull a file by id.  If the file was pushed with a password, it is required to pull the file.  When ID is omitted, pulls the most recently pushed file.",
+        flags: &[
+            Flag::switch(
+                "anon",
+                "pull without passing credentials.  only public files (pushed with private=false) can be pulled anonymously.",
+            ),
+            Flag::value("pw", "PW", "password the file was pushed with, if any."),
+            Flag::value(
+                "pw-file",
+                "FILE",
+                "read the password from the first line of a file.",
+            )
+            .complete(Complete::Path),
+            Flag::value(
+                "pw-env",
+                "VAR",
+                "read the password from an environment variable.",
+            ),
+            Flag::switch("pw-prompt", "prompt for the password.").fixed(),
+            Flag::value(
+                "wait",
+                "TIMEOUT",
+                "wait for the file to be pushed, polling with backoff, and give up after TIMEOUT if given.  An ID ending in ':' waits for the newest file with that prefix.  Exits with status 124 on timeout.  Format: \\d+(h|m|s)",
+            )
+            .optional(),
+            Flag::value(
+                "name",
+                "NAME",
+                "pull the newest file pushed with --name NAME, instead of an ID.",
+            ),
+            Flag::switch(
+                "to-clipboard",
+                "copy the file to the clipboard instead of writing it to stdout.",
+            )
+            .fixed(),
+            Flag::value(
+                "out",
+                "FILE",
+                "write the file to FILE instead of stdout.",
+            )
+            .short('o')
+            .complete(Complete::Path),
+            Flag::switch(
+                "remote-name",
+                "write the file to the name it was pushed with, in the current directory, instead of stdout.  Names starting with . aren't used, and an existing file isn't replaced without --clobber.",
+            )
+            .short('O')
+            .fixed(),
+            Flag::switch(
+                "clobber",
+                "let --remote-name replace a file that's already there.",
+            )
+            .fixed(),
+            Flag::value(
+                "range",
+                "START-END",
+                "only pull bytes START through END of the file, counting from 0.  Without END, pulls to the end of the file.  Uses an HTTP Range request, so a burn file isn't consumed when the server supports them.  Format: \\d+-\\d*",
+            ),
+            Flag::value(
+                "head",
+                "SIZE",
+                "only pull the first SIZE bytes of the file, eg. 1k, to peek at it.  Format: \\d+(k|m|g)",
+            ),
+            Flag::switch(
+                "resume",
+                "continue an interrupted pull into the --out or --remote-name file, fetching only the bytes it's missing.  Fails if the file is longer than the pushed one, or was pushed with --split.",
+            )
+            .fixed(),
+            Flag::value(
+                "limit-rate",
+                "RATE",
+                "download at most RATE bytes per second, eg. 500k.  Format: \\d+(k|m|g)",
+            ),
+        ],
+        positionals: &[Positional {
+            name: "ID",
+            required: false,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file to pull.  If you pushed the file with a prefix, you must include that prefix.  Defaults to the id of the most recently pushed file.  Also accepts an ID:PASSWORD token from push --gen-pw --pw-token, a share token from push --share, or a reference to your push history like @1 or @last.",
+        }],
+        examples: &[
+            "scratch pull c869d7cc",
+            "scratch pull --anon creds.aws:f0022e5a",
+            "scratch pull --to-clipboard",
+            "scratch pull --head 1k 97a292d7",
+            "scratch pull --resume -o db.sql.gz 97a292d7",
+            "scratch pull --wait=10m creds.aws:",
+            "scratch pull --name deploy-config",
+            "scratch pull @2",
+        ],
+    },
+    Command {
+        name: "ls",
+        summary: "List all file metadata",
+        description: "List file ids and their metadata.",
+        flags: &[],
+        positionals: &[],
+        examples: &[],
+    },
+    Command {
+        name: "rm",
+        summary: "Remove a file by id",
+        description: "Delete a file by id.",
+        flags: &[],
+        positionals: &[Positional {
+            name: "ID",
+            required: true,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file to delete.  If you pushed the file with a prefix, you must include that prefix.  Deletion does not require a password.  Also accepts a reference to your push history like @1 or @last.",
+        }],
+        examples: &[
+            "scratch rm c869d7cc",
+            "scratch rm creds.aws:f0022e5a",
+            "scratch rm @last",
+        ],
+    },
+    Command {
+        name: "history",
+        summary: "List files you pushed",
+        description: "List the files pushed from this machine that haven't expired, newest first.  Each entry is numbered, and @1, @2, ... or @last can be used in place of an id with pull, rm and qr.  The history is kept in ~/.kilobytetools/history.toml, and entries are dropped once the file's lifetime has passed.",
+        flags: &[],
+        positionals: &[],
+        examples: &["scratch history", "scratch pull @1"],
+    },
+    Command {
+        name: "stats",
+        summary: "Get usage stats for your account",
+        description: "List usage and capacity stats for your account.",
+        flags: &[],
+        positionals: &[],
+        examples: &[],
+    },
+    Command {
+        name: "watch",
+        summary: "Pull new files as they are pushed",
+        description: "Keep polling for files and pull each new one, until interrupted.  Files are written to stdout one after another, or into a directory named after their ids, in which case the path of each file is printed.  Files that fail to pull are retried with a growing delay, and files with a password are skipped, since watch doesn't pass one.",
+        flags: &[
+            Flag::value(
+                "prefix",
+                "PREFIX",
+                "Only pull files whose id starts with PREFIX",
+            ),
+            Flag::value(
+                "dir",
+                "DIR",
+                "Write each file into DIR instead of stdout.  Files appear once they are complete",
+            )
+            .complete(Complete::Path),
+            Flag::switch(
+                "delete",
+                "Delete each file after it's pulled, so the next watcher doesn't pull it again",
+            ),
+            Flag::switch(
+                "existing",
+                "Also pull the files that are already there when the watcher starts, which are skipped otherwise",
+            ),
+            Flag::value(
+                "limit-rate",
+                "RATE",
+                "Download at most RATE bytes per second, eg. 500k.  Format: \\d+(k|m|g)",
+            ),
+        ],
+        positionals: &[],
+        examples: &[
+            "scratch watch --prefix logs: --dir ./logs --delete",
+            "scratch watch --prefix alerts: >> alerts.log",
+        ],
+    },
+    Command {
+        name: "info",
+        summary: "Show what's known about a file without pulling it",
+        description: "Check whether a file exists and show its size, expiry and whether it is burn, private or password protected, without pulling it.  Asks the server with HEAD requests and the file list, and fills in what the server doesn't say from your push history.  HEAD requests are assumed not to consume burn files.  Fields nobody knows are left out.  Exits with status 0 when the file exists, 2 when it doesn't, and 1 on other errors.",
+        flags: &[],
+        positionals: &[Positional {
+            name: "ID",
+            required: true,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file.  If you pushed the file with a prefix, you must include that prefix.  Also accepts a reference to your push history like @1 or @last.",
+        }],
+        examples: &["scratch info c869d7cc", "scratch info --out-format js @1"],
+    },
+    Command {
+        name: "touch",
+        summary: "Change how long a file lives",
+        description: "Give an existing file a new lifetime, counted from now, without pulling it or changing its id.  If the server can't change a file in place, the file is pulled and pushed again with the new lifetime and its other attributes, the old file is deleted, and the new id is printed.  A copy keeps the prefix or name of the old id, and its place in your push history.  For a file pushed with --split only the manifest is changed, the parts keep their own lifetime.",
+        flags: &[
+            Flag::value(
+                "lifetime",
+                "LIFETIME",
+                "The new lifetime of the file, from now, eg. 30m.  Required.  Format: \\d+(h|m|s)",
+            ),
+            Flag::value(
+                "current-pw",
+                "PW",
+                "The password the file was pushed with, needed if the file has to be pushed again.  An ID:PASSWORD token works too.",
+            ),
+            Flag::switch(
+                "allow-secrets",
+                "Push a file that isn't private again even if it looks like it contains credentials, such as keys or tokens",
+            ),
+        ],
+        positionals: &[Positional {
+            name: "ID",
+            required: true,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file.  If you pushed the file with a prefix, you must include that prefix.  Also accepts a reference to your push history like @1 or @last.",
+        }],
+        examples: &["scratch touch c869d7cc --lifetime 30m", "scratch touch @1 --lifetime 2h"],
+    },
+    Command {
+        name: "chmod",
+        summary: "Change whether a file is private or password protected",
+        description: "Make an existing file private or public, or give it a new password, without changing anything else about it.  Like touch, falls back to pulling the file and pushing it again when the server can't change it in place, and prints the new id.",
+        flags: &[
+            Flag::switch("private", "Whether the file can be read by anyone."),
+            Flag::value(
+                "pw",
+                "PASSWORD",
+                "The new password of the file.  Format: [a-zA-Z0-9._-]{1,20}  Note: visible to other users in ps and your shell history, prefer the options below",
+            ),
+            Flag::value(
+                "pw-file",
+                "FILE",
+                "Read the new password from the first line of a file",
+            )
+            .complete(Complete::Path),
+            Flag::value(
+                "pw-env",
+                "VAR",
+                "Read the new password from an environment variable",
+            ),
+            Flag::switch("pw-prompt", "Prompt for the new password").fixed(),
+            Flag::switch(
+                "gen-pw",
+                "Generate a random new password, and print it next to the id",
+            )
+            .fixed(),
+            Flag::value(
+                "current-pw",
+                "PW",
+                "The password the file was pushed with.  An ID:PASSWORD token works too.",
+            ),
+            Flag::switch(
+                "allow-secrets",
+                "Push the file again when it has to be, even if it isn't private and looks like it contains credentials",
+            ),
+        ],
+        positionals: &[Positional {
+            name: "ID",
+            required: true,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file.  If you pushed the file with a prefix, you must include that prefix.  Also accepts a reference to your push history like @1 or @last.",
+        }],
+        examples: &[
+            "scratch chmod c869d7cc --no-private",
+            "scratch chmod @1 --gen-pw --current-pw hunter2",
+        ],
+    },
+    Command {
+        name: "cp",
+        summary: "Copy a file to another profile",
+        description: "Copy a file between profiles, eg. from a personal account to a team account.  The file is streamed from one endpoint to the other without a temporary file, and the copy keeps the burn, private, password, lifetime left and prefix of the original unless they're given.  The copy is checked against the original's hash from your push history, or else pulled again, and a copy that doesn't match is deleted.  Prints the id of the copy.  Burn files, and files that might be, are only copied with --allow-burn, since copying uses them up.  Files pushed with --split can't be copied.",
+        flags: COPY_FLAGS,
+        positionals: &[
+            Positional {
+                name: "SRC_PROFILE:ID",
+                required: true,
+                repeated: false,
+                complete: Complete::FileIds,
+                help: "The file to copy, as a profile name and an id.  Without a profile, or with the profile default, the id is looked up with the api_key and endpoint at the top of the config file.  Also accepts a reference to your push history like @1 or @last.",
+            },
+            Positional {
+                name: "DST_PROFILE",
+                required: true,
+                repeated: false,
+                complete: Complete::Nothing,
+                help: "The profile to copy the file to, from a [profile.NAME] section in the config file, or default.",
+            },
+        ],
+        examples: &["scratch cp team:c869d7cc default", "scratch cp @1 team --lifetime 1h"],
+    },
+    Command {
+        name: "mv",
+        summary: "Move a file to another profile",
+        description: "Like cp, then deletes the original, but only once the copy has been checked.  A burn copy of a file that isn't in your push history can't be pulled again to check it, so the original is kept.",
+        flags: COPY_FLAGS,
+        positionals: &[
+            Positional {
+                name: "SRC_PROFILE:ID",
+                required: true,
+                repeated: false,
+                complete: Complete::FileIds,
+                help: "The file to copy, as a profile name and an id.  Without a profile, or with the profile default, the id is looked up with the api_key and endpoint at the top of the config file.  Also accepts a reference to your push history like @1 or @last.",
+            },
+            Positional {
+                name: "DST_PROFILE",
+                required: true,
+                repeated: false,
+                complete: Complete::Nothing,
+                help: "The profile to copy the file to, from a [profile.NAME] section in the config file, or default.",
+            },
+        ],
+        examples: &["scratch mv c869d7cc team"],
+    },
+    Command {
+        name: "qr",
+        summary: "Print a file url as a QR code",
+        description: "Print the url of an existing file as a QR code, using unicode block characters.  The QR code doesn't include the file's password.",
+        flags: &[],
+        positionals: &[Positional {
+            name: "ID",
+            required: true,
+            repeated: false,
+            complete: Complete::FileIds,
+            help: "The id of the file.  If you pushed the file with a prefix, you must include that prefix.  Also accepts a reference to your push history like @1 or @last.",
+        }],
+        examples: &["scratch qr c869d7cc"],
+    },
+    Command {
+        name: "bootstrap",
+        summary: "Create a valid config file",
+        description: "Creates a minimal valid config file to use the service.  By default this writes to ~/.kilobytetools/config.toml.",
+        flags: &[Flag::switch(
+            "stdout",
+            "Write to stdout instead of the default path.",
+        )],
+        positionals: &[],
+        examples: &[],
+    },
+    Command {
+        name: "completions",
+        summary: "Print a shell completion script",
+        description: "Print a completion script for your shell.  Completing the ID of pull, rm, info, touch, chmod, qr, cp and mv lists your files, so it uses the api key and endpoint at the top of your config file, even for the source of cp and mv.",
+        flags: &[],
+        positionals: &[Positional {
+            name: "SHELL",
+            required: true,
+            repeated: false,
+            complete: Complete::Choices(SHELLS),
+            help: "The shell to generate completions for.  One of [bash, zsh, fish]",
+        }],
+        examples: &[
+            "scratch completions bash > /etc/bash_completion.d/scratch",
+            "scratch completions zsh > \"${fpath[1]}/_scratch\"",
+            "scratch completions fish > ~/.config/fish/completions/scratch.fish",
+        ],
+    },
+];
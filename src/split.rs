@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Write};
+
+use super::api::{self, ClientOpts, DeleteArgs, PullArgs, PushArgs};
+use super::history;
+use super::util::{self, InputMode, Size};
+
+/// First line of every manifest
+const MAGIC: &[u8] = b"# scratch split manifest v1\n";
+
+/// The content type manifests are pushed with, which marks them on the
+/// server, so a pushed file that merely starts like one is left alone
+pub const CONTENT_TYPE: &str = "application/vnd.scratch.split-manifest";
+
+/// Most parts an input is split into, which keeps manifests small enough to
+/// hold in memory
+const MAX_PARTS: u64 = 10_000;
+/// Longest manifest a pull holds back, with room for MAX_PARTS parts
+const MAX_MANIFEST_LEN: usize = 4 << 20;
+
+#[derive(Serialize, Deserialize)]
+pub struct Part {
+    pub id: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// The parts of a split input, in order, with the size and hash of the
+/// whole thing
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub size: u64,
+    pub sha256: String,
+    #[serde(default, rename = "part")]
+    pub parts: Vec<Part>,
+}
+
+impl Manifest {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        Ok([MAGIC, text.as_bytes()].concat())
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, io::Error> {
+        let malformed = |err: &dyn std::fmt::Display| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed split manifest: {}", err),
+            )
+        };
+        if data.len() > MAX_MANIFEST_LEN {
+            return Err(malformed(&"too long"));
+        }
+        let body = data
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| malformed(&"no manifest header"))?;
+        let text = std::str::from_utf8(body).map_err(|err| malformed(&err))?;
+        toml::from_str(text).map_err(|err| malformed(&err))
+    }
+}
+
+pub fn is_manifest(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Whether a file was pushed as a manifest, by the content type the server
+/// serves it with, or by the push history for servers that don't keep
+/// content types
+pub fn is_marked(id: &str, content_type: Option<&str>) -> bool {
+    let marked = |content_type: Option<&str>| {
+        content_type.is_some_and(|content_type| content_type.starts_with(CONTENT_TYPE))
+    };
+    marked(content_type)
+        || history::list()
+            .unwrap_or_default()
+            .iter()
+            .any(|entry| entry.id == id && marked(entry.content_type.as_deref()))
+}
+
+/// Delete a file, and if it's a manifest, the parts it lists first.  Only
+/// the api key is used, since the file may not have the password of the
+/// push that replaces it, and a manifest is only pulled when it's marked as
+/// one, so deleting other files doesn't pull them.  A manifest that can't
+/// be pulled is kept, so its parts aren't lost track of.
+pub fn delete(
+    endpoint: &str,
+    api_key: &str,
+    id: &str,
+    opts: ClientOpts,
+) -> Result<(), api::ErrorKind> {
+    // a file with a password may not say what it is without it, but the
+    // history still knows the manifests pushed from here
+    let content_type = api::stored(endpoint, api_key, id, None, opts)
+        .ok()
+        .and_then(|(_, content_type)| content_type);
+    if is_marked(id, content_type.as_deref()) {
+        let mut data = Vec::new();
+        let pull = PullArgs::new(
+            endpoint.into(),
+            Some(id.into()),
+            Some(api_key.into()),
+            None,
+            None,
+            &mut data,
+        );
+        api::pull(pull, opts)?;
+        if is_manifest(&data) {
+            let manifest = Manifest::parse(&data).map_err(api::ErrorKind::LocalIoError)?;
+            for part in &manifest.parts {
+                let delete = DeleteArgs::new(api_key.into(), endpoint.into(), part.id.clone());
+                if let Err(err) = api::delete(delete, opts) {
+                    eprintln!("unable to delete part {}: {}", part.id, err);
+                }
+            }
+        }
+    }
+    let delete = DeleteArgs::new(api_key.into(), endpoint.into(), id.into());
+    api::delete(delete, opts).map(|_| ())
+}
+
+fn sha256(data: &[u8]) -> String {
+    util::hex(&Sha256::digest(data))
+}
+
+/// Push the input in parts of at most part_size, each built into a push by
+/// make_args.  If a part fails, the parts already pushed are deleted, since
+/// they can't be pulled without a manifest.
+pub fn push_parts<F>(
+    input: InputMode,
+    part_size: Size,
+    api_key: &str,
+    endpoint: &str,
+    opts: ClientOpts,
+    make_args: F,
+) -> Result<Manifest, api::ErrorKind>
+where
+    F: Fn(InputMode) -> PushArgs,
+{
+    if input.size().div_ceil(part_size.0) > MAX_PARTS {
+        return Err(api::ErrorKind::Rejected(format!(
+            "--split would make more than {} parts, use a bigger size",
+            MAX_PARTS
+        )));
+    }
+    let mut reader: Box<dyn Read> = match input {
+        InputMode::Buffer(buf) => Box::new(io::Cursor::new(buf)),
+        InputMode::File(file) => Box::new(file),
+        InputMode::Stream(reader, _) => reader,
+    };
+    let mut manifest = Manifest {
+        size: 0,
+        sha256: String::new(),
+        parts: Vec::new(),
+    };
+    let mut hasher = Sha256::new();
+    let pushed = loop {
+        let mut part = Vec::new();
+        if let Err(err) = (&mut reader).take(part_size.0).read_to_end(&mut part) {
+            break Err(api::ErrorKind::LocalIoError(err));
+        }
+        if part.is_empty() {
+            break Ok(());
+        }
+        hasher.update(&part);
+        let (size, hash) = (part.len() as u64, sha256(&part));
+        let mut created_id = None;
+        let result = api::push(make_args(InputMode::Buffer(part)), opts, |id, _| {
+            created_id = Some(id.to_string())
+        });
+        // a part that was created but not uploaded still needs cleaning up
+        if let Some(id) = created_id {
+            manifest.parts.push(Part {
+                id,
+                size,
+                sha256: hash,
+            });
+        }
+        if let Err(err) = result {
+            break Err(err);
+        }
+        manifest.size += size;
+    };
+    if let Err(err) = pushed {
+        for part in &manifest.parts {
+            let delete = DeleteArgs::new(api_key.into(), endpoint.into(), part.id.clone());
+            if let Err(err) = api::delete(delete, opts) {
+                eprintln!("unable to delete part {}: {}", part.id, err);
+            }
+        }
+        return Err(err);
+    }
+    manifest.sha256 = util::hex(&hasher.finalize());
+    Ok(manifest)
+}
+
+/// Pull each part of a manifest, check it against the manifest and write it
+/// to output.  Parts are checked before they're written, so output only
+/// ever holds data that came from the manifest.
+pub fn pull_parts<W>(
+    manifest: &Manifest,
+    endpoint: &str,
+    api_key: Option<&str>,
+    pw: Option<&str>,
+    opts: ClientOpts,
+    mut output: W,
+) -> Result<(), api::ErrorKind>
+where
+    W: Write,
+{
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    for (i, part) in manifest.parts.iter().enumerate() {
+        let mut data = Vec::new();
+        let args = PullArgs::new(
+            endpoint.into(),
+            Some(part.id.clone()),
+            api_key.map(String::from),
+            pw.map(String::from),
+            None,
+            &mut data,
+        );
+        api::pull(args, opts)?;
+        if data.len() as u64 != part.size || sha256(&data) != part.sha256 {
+            return Err(api::ErrorKind::Corrupt(format!(
+                "part {} ({}) doesn't match the manifest",
+                i + 1,
+                part.id
+            )));
+        }
+        hasher.update(&data);
+        size += part.size;
+        output
+            .write_all(&data)
+            .map_err(api::ErrorKind::LocalIoError)?;
+    }
+    if size != manifest.size || util::hex(&hasher.finalize()) != manifest.sha256 {
+        return Err(api::ErrorKind::Corrupt(
+            "the joined parts don't match the manifest".into(),
+        ));
+    }
+    output.flush().map_err(api::ErrorKind::LocalIoError)
+}
+
+/// Passes a pull through to another writer, unless the pulled file is a
+/// manifest, which is held back so its parts can be pulled instead.  Only
+/// MAX_MANIFEST_LEN bytes are held back, since a longer file isn't one.
+pub struct Sniff<W> {
+    inner: W,
+    head: Vec<u8>,
+    decided: bool,
+    too_long: bool,
+}
+
+impl<W: Write> Sniff<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            head: Vec::new(),
+            decided: false,
+            too_long: false,
+        }
+    }
+
+    /// The manifest, if the pulled file was one and marked as one
+    pub fn finish(mut self, marked: bool) -> Result<Option<Vec<u8>>, io::Error> {
+        if marked && self.too_long {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed split manifest: too long",
+            ));
+        }
+        if marked && is_manifest(&self.head) {
+            return Ok(Some(self.head));
+        }
+        // files shorter than the magic line are never decided
+        self.inner.write_all(&self.head)?;
+        self.inner.flush()?;
+        Ok(None)
+    }
+}
+
+impl<W: Write> Write for Sniff<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.decided && !is_manifest(&self.head) {
+            return self.inner.write(buf);
+        }
+        self.head.extend_from_slice(buf);
+        if self.head.len() > MAX_MANIFEST_LEN {
+            // passed through from here on, since the head no longer matches
+            self.too_long = true;
+            self.inner.write_all(&self.head)?;
+            self.head.clear();
+            return Ok(buf.len());
+        }
+        let undecided = self.head.len() < MAGIC.len() && MAGIC.starts_with(&self.head);
+        if !self.decided && !undecided {
+            self.decided = true;
+            if !is_manifest(&self.head) {
+                self.inner.write_all(&self.head)?;
+                self.head.clear();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Manifest {
+        Manifest {
+            size: 3,
+            sha256: sha256(b"abc"),
+            parts: vec![
+                Part {
+                    id: "p1".into(),
+                    size: 2,
+                    sha256: sha256(b"ab"),
+                },
+                Part {
+                    id: "p2".into(),
+                    size: 1,
+                    sha256: sha256(b"c"),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn manifests_round_trip() {
+        let data = manifest().to_bytes().unwrap();
+        assert!(is_manifest(&data));
+        let parsed = Manifest::parse(&data).unwrap();
+        assert_eq!(parsed.size, 3);
+        assert_eq!(parsed.sha256, sha256(b"abc"));
+        let ids: Vec<&str> = parsed.parts.iter().map(|part| part.id.as_str()).collect();
+        assert_eq!(ids, ["p1", "p2"]);
+    }
+
+    #[test]
+    fn malformed_manifests_are_rejected() {
+        let cases: [&[u8]; 6] = [
+            b"",
+            b"# scratch",
+            b"size = 3\nsha256 = \"\"\n",
+            b"# scratch split manifest v1\n",
+            b"# scratch split manifest v1\nsize = \"three\"\nsha256 = \"\"\n",
+            b"# scratch split manifest v1\nsize = 3\nsha256 = \"\xff\"\n",
+        ];
+        for data in cases {
+            let err = Manifest::parse(data)
+                .err()
+                .expect("parsed a malformed manifest");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn only_marked_manifests_are_held_back() {
+        let data = manifest().to_bytes().unwrap();
+        for marked in [true, false] {
+            let mut out = Vec::new();
+            let mut sniff = Sniff::new(&mut out);
+            for chunk in data.chunks(5) {
+                sniff.write_all(chunk).unwrap();
+            }
+            let held = sniff.finish(marked).unwrap();
+            match marked {
+                true => assert!(held.as_deref() == Some(&data[..]) && out.is_empty()),
+                false => assert!(held.is_none() && out == data),
+            }
+        }
+        // a file that only starts like a manifest is passed on once it's
+        // longer than one can be
+        let mut data = manifest().to_bytes().unwrap();
+        data.resize(MAX_MANIFEST_LEN + 10, b'#');
+        let mut out = Vec::new();
+        let mut sniff = Sniff::new(&mut out);
+        for chunk in data.chunks(4096) {
+            sniff.write_all(chunk).unwrap();
+        }
+        assert!(sniff.finish(true).is_err());
+        assert_eq!(out, data);
+        let mut out = Vec::new();
+        let mut sniff = Sniff::new(&mut out);
+        sniff.write_all(&data).unwrap();
+        assert!(sniff.finish(false).unwrap().is_none());
+        assert_eq!(out, data);
+        for data in [&b""[..], b"# scr", b"plain data"] {
+            let mut out = Vec::new();
+            let mut sniff = Sniff::new(&mut out);
+            sniff.write_all(data).unwrap();
+            assert!(sniff.finish(true).unwrap().is_none());
+            assert_eq!(out, data);
+        }
+    }
+}
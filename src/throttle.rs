@@ -0,0 +1,96 @@
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::util::Size;
+
+/// Keeps a transfer under a number of bytes per second, by sleeping
+/// whenever it gets ahead of the rate
+pub struct Throttle {
+    rate: Option<u64>,
+    start: Instant,
+    moved: u64,
+}
+
+impl Throttle {
+    pub fn new(limit: Option<Size>) -> Self {
+        Self {
+            rate: limit.map(|limit| limit.0),
+            start: Instant::now(),
+            moved: 0,
+        }
+    }
+
+    /// The most to move at once, so the sleeps stay short and the rate even
+    fn chunk(&self, len: usize) -> usize {
+        match self.rate {
+            Some(rate) => len.min((rate / 10).max(1) as usize),
+            None => len,
+        }
+    }
+
+    fn pace(&mut self, bytes: usize) {
+        let rate = match self.rate {
+            Some(rate) => rate,
+            None => return,
+        };
+        self.moved += bytes as u64;
+        let due = Duration::from_secs_f64(self.moved as f64 / rate as f64);
+        let elapsed = self.start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+    }
+}
+
+pub struct Reader<R> {
+    inner: R,
+    throttle: Throttle,
+}
+
+impl<R> Reader<R> {
+    pub fn new(inner: R, limit: Option<Size>) -> Self {
+        Self {
+            inner,
+            throttle: Throttle::new(limit),
+        }
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.throttle.chunk(buf.len());
+        let n = self.inner.read(&mut buf[..len])?;
+        self.throttle.pace(n);
+        Ok(n)
+    }
+}
+
+pub struct Writer<W> {
+    inner: W,
+    throttle: Throttle,
+}
+
+impl<W> Writer<W> {
+    pub fn new(inner: W, limit: Option<Size>) -> Self {
+        Self {
+            inner,
+            throttle: Throttle::new(limit),
+        }
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.throttle.chunk(buf.len());
+        let n = self.inner.write(&buf[..len])?;
+        self.throttle.pace(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,301 @@
+use sha2::{Digest, Sha256};
+use std::{
+    cell::RefCell,
+    io::{self, Read},
+    rc::Rc,
+};
+
+use super::api::{self, ClientOpts, DeleteArgs, PullArgs, PushArgs};
+use super::args;
+use super::blind;
+use super::change;
+use super::history;
+use super::push;
+use super::secrets::Scanner;
+use super::split;
+use super::util::{self, InputMode, Lifetime};
+
+/// Where a file is copied from or to
+pub struct Account<'a> {
+    pub api_key: &'a str,
+    pub endpoint: &'a str,
+}
+
+/// Attributes the copy gets instead of the original's.  Attributes that
+/// are None are kept from the original.
+pub struct Overrides {
+    pub lifetime: Option<Lifetime>,
+    pub private: Option<bool>,
+    pub burn: Option<bool>,
+    pub pw: Option<String>,
+}
+
+/// How a file is copied
+pub struct Mode {
+    /// Delete the original once the copy is checked, which makes a move
+    pub remove_source: bool,
+    /// Copy files that are or might be burn files, which uses them up
+    pub allow_burn: bool,
+    /// What a copy that isn't private is checked for secrets with, unless they're allowed
+    pub secrets: Option<Scanner>,
+}
+
+/// Hashes the bytes read through it, so the pull can be checked once the
+/// push has consumed the reader
+struct HashReader<R> {
+    inner: R,
+    hasher: Rc<RefCell<Sha256>>,
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.borrow_mut().update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Copy a file to another profile, and with remove_source delete the
+/// original once the copy is checked, then print the copy's id
+pub fn run(
+    copy: args::CopyArgs,
+    remove_source: bool,
+    json: bool,
+    opts: ClientOpts,
+) -> Result<(), api::ErrorKind> {
+    let id = blind(copy.id);
+    let dst = blind(copy.dst);
+    let (src_api_key, src_endpoint) = (blind(copy.src.api_key), blind(copy.src.endpoint));
+    let (dst_api_key, dst_endpoint) = (blind(dst.api_key), blind(dst.endpoint));
+    let src = Account {
+        api_key: &src_api_key,
+        endpoint: &src_endpoint,
+    };
+    let dst_account = Account {
+        api_key: &dst_api_key,
+        endpoint: &dst_endpoint,
+    };
+    let generated_pw = match copy.gen_pw {
+        true => copy.pw.clone(),
+        false => None,
+    };
+    let overrides = Overrides {
+        lifetime: copy.lifetime,
+        private: copy.private,
+        burn: copy.burn,
+        pw: copy.pw,
+    };
+    let mode = Mode {
+        remove_source,
+        allow_burn: copy.allow_burn,
+        secrets: copy.secrets,
+    };
+    let new_id = self::copy(
+        &id,
+        copy.current_pw,
+        src,
+        dst_account,
+        overrides,
+        mode,
+        opts,
+    )?;
+    if json {
+        let mut fields = vec![
+            format!("\"id\": {}", util::json_string(&new_id)),
+            format!("\"profile\": {}", util::json_string(&dst.name)),
+        ];
+        if let Some(pw) = &generated_pw {
+            fields.push(format!("\"pw\": {}", util::json_string(pw)));
+        }
+        println!("{{{}}}", fields.join(", "));
+    } else {
+        println!(
+            "{}",
+            push::id_line("", &new_id, generated_pw.as_deref(), false)
+        );
+    }
+    Ok(())
+}
+
+/// Copy a file from one account to another, streaming the pull into the
+/// push, and return the id of the copy.  The copy keeps the prefix and
+/// attributes of the original unless they're overridden.  The copy is
+/// checked before anything else happens, and a bad copy is deleted, so with
+/// remove_source the original is only deleted once the copy is known good.
+/// Split files aren't copied, and burn files only with allow_burn, since
+/// the copy has to pull them.
+pub fn copy(
+    id: &str,
+    current_pw: Option<String>,
+    src: Account,
+    dst: Account,
+    overrides: Overrides,
+    mode: Mode,
+    opts: ClientOpts,
+) -> Result<String, api::ErrorKind> {
+    let info = change::lookup(id, src.api_key, src.endpoint, opts)?;
+    if split::is_marked(id, info.content_type.as_deref()) {
+        return Err(api::ErrorKind::ServerError(
+            "a split file can't be copied, pull it and push it again with --split",
+        ));
+    }
+    match (info.burn, mode.allow_burn) {
+        (Some(false), _) | (_, true) => {}
+        (Some(true), false) => {
+            return Err(api::ErrorKind::ServerError(
+                "copying a burn file uses it up, pass --allow-burn to copy it anyway",
+            ))
+        }
+        (None, false) => {
+            return Err(api::ErrorKind::ServerError(
+                "the file might be a burn file, which copying uses up, pass --allow-burn to copy it anyway",
+            ))
+        }
+    }
+    let lifetime = overrides
+        .lifetime
+        .or_else(|| change::remaining_lifetime(&info));
+    let burn = overrides.burn.or(info.burn);
+    let private = overrides.private.or(info.private);
+    let pw = overrides.pw.or_else(|| current_pw.clone());
+
+    let pull = PullArgs::new(
+        src.endpoint.into(),
+        Some(id.into()),
+        Some(src.api_key.into()),
+        current_pw,
+        None,
+        io::sink(),
+    );
+    let (body, size) = api::open(pull, opts)?;
+    let hasher = Rc::new(RefCell::new(Sha256::new()));
+    let mut body = HashReader {
+        inner: body,
+        hasher: hasher.clone(),
+    };
+    let mut input = match size {
+        Some(size) => InputMode::Stream(Box::new(body), size),
+        None => {
+            // a push needs the length up front
+            let mut buf = Vec::new();
+            body.read_to_end(&mut buf)
+                .map_err(api::ErrorKind::LocalIoError)?;
+            InputMode::Buffer(buf)
+        }
+    };
+    if let Some(secrets) = mode.secrets.as_ref().filter(|_| private != Some(true)) {
+        secrets
+            .check(&mut input)
+            .map_err(|err| api::ErrorKind::Rejected(err.to_string()))?;
+    }
+    let size = input.size();
+    let push = PushArgs::new(
+        dst.api_key.into(),
+        dst.endpoint.into(),
+        input,
+        burn,
+        private,
+        pw.clone(),
+        change::prefix(id),
+        lifetime.clone(),
+    )
+    .file(info.file_name.clone(), info.content_type.clone());
+    let mut new_id = None;
+    api::push(push, opts, |id, _| new_id = Some(id.to_string()))?;
+    let new_id = new_id.ok_or(api::ErrorKind::ServerError(
+        "malformed resp from server: no id",
+    ))?;
+
+    let sha256 = util::hex(&hasher.take().finalize());
+    // pulling a burn copy to check it would use it up
+    let checked = check(
+        &dst,
+        &new_id,
+        pw.as_deref(),
+        (size, &sha256),
+        info.sha256.as_deref(),
+        burn == Some(false),
+        opts,
+    )
+    .and_then(|verified| match verified || !mode.remove_source {
+        true => Ok(()),
+        false => Err(api::ErrorKind::ServerError(
+            "the copy is a burn file and the original's hash isn't known, so the copy can't be checked and the original was kept",
+        )),
+    });
+    if let Err(err) = checked {
+        let delete = DeleteArgs::new(dst.api_key.into(), dst.endpoint.into(), new_id.clone());
+        if let Err(err) = api::delete(delete, opts) {
+            eprintln!("unable to delete the bad copy {}: {}", new_id, err);
+        }
+        return Err(err);
+    }
+
+    if let Some(source) = info.source {
+        let _ = history::record(history::Entry {
+            id: new_id.clone(),
+            endpoint: dst.endpoint.into(),
+            pushed: history::now(),
+            expires: history::expiry(lifetime.as_ref()),
+            size,
+            sha256,
+            lifetime: lifetime.map(|lifetime| lifetime.0),
+            burn,
+            private,
+            source,
+            file_name: info.file_name,
+            content_type: info.content_type,
+        });
+    }
+    if mode.remove_source {
+        let delete = DeleteArgs::new(src.api_key.into(), src.endpoint.into(), id.into());
+        api::delete(delete, opts)?;
+        let _ = history::forget(id);
+    }
+    Ok(new_id)
+}
+
+/// Check a copy against the original: its stored size, then its data by
+/// the hash of what was pulled, which has to match the original's hash
+/// from the push history or, when there isn't one, the hash of the copy
+/// pulled again.  Returns false if the copy could only be checked by size,
+/// because it can't be pulled again without using it up.
+fn check(
+    dst: &Account,
+    new_id: &str,
+    pw: Option<&str>,
+    pulled: (u64, &str),
+    expected: Option<&str>,
+    repull: bool,
+    opts: ClientOpts,
+) -> Result<bool, api::ErrorKind> {
+    let (stored, _) = api::stored(dst.endpoint, dst.api_key, new_id, pw, opts)?;
+    if stored != Some(pulled.0) {
+        return Err(api::ErrorKind::ServerError(
+            "the copy doesn't have the size of the original, so the original was kept",
+        ));
+    }
+    let expected = match expected {
+        Some(expected) => expected.to_string(),
+        None if !repull => return Ok(false),
+        None => {
+            let mut hasher = Sha256::new();
+            let pull = PullArgs::new(
+                dst.endpoint.into(),
+                Some(new_id.into()),
+                Some(dst.api_key.into()),
+                pw.map(String::from),
+                None,
+                util::HashWriter(&mut hasher),
+            );
+            api::pull(pull, opts)?;
+            util::hex(&hasher.finalize())
+        }
+    };
+    match expected == pulled.1 {
+        true => Ok(true),
+        false => Err(api::ErrorKind::ServerError(
+            "the copy doesn't match the original, so the original was kept",
+        )),
+    }
+}
@@ -1,17 +1,23 @@
 use core::str::FromStr;
 use lazy_static::lazy_static;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use std::{
     error::Error as StdError,
     fmt::Display,
     fs,
-    io::{self, Read},
-    path::Path,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+use super::clipboard;
+
 #[derive(Debug)]
 pub enum Error {
     MalformedArgument(&'static str, String, String),
+    /// Well formed, but not a value that can be used
+    OutOfRange(&'static str, String, String),
 }
 
 impl StdError for Error {}
@@ -22,6 +28,25 @@ impl Display for Error {
             Error::MalformedArgument(name, unexp, exp) => {
                 write!(f, "{} was {} but must match {}", name, unexp, exp)
             }
+            Error::OutOfRange(name, unexp, exp) => {
+                write!(f, "{} was {} but {}", name, unexp, exp)
+            }
+        }
+    }
+}
+
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+    Clipboard,
+}
+
+impl InputSource {
+    pub fn open(self) -> Result<InputMode, io::Error> {
+        match self {
+            InputSource::Stdin => InputMode::from_stdin(),
+            InputSource::File(name) => InputMode::from_filename(name),
+            InputSource::Clipboard => InputMode::from_buffer(clipboard::read()?),
         }
     }
 }
@@ -29,6 +54,8 @@ impl Display for Error {
 pub enum InputMode {
     Buffer(Vec<u8>),
     File(fs::File),
+    /// A body of a known length, read once, eg. a file pulled from elsewhere
+    Stream(Box<dyn Read>, u64),
 }
 
 impl InputMode {
@@ -45,21 +72,246 @@ impl InputMode {
         Ok(InputMode::File(fs::File::open(name)?))
     }
 
+    pub fn into_bytes(self) -> Result<Vec<u8>, io::Error> {
+        match self {
+            InputMode::Buffer(buf) => Ok(buf),
+            InputMode::File(mut file) => {
+                let mut buf = Vec::new();
+                file.read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+            InputMode::Stream(mut reader, _) => {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+        }
+    }
+
+    /// Hex sha256 of the data, leaving a file positioned at its start.  A
+    /// stream can't be read twice, so it's read into a buffer.
+    pub fn sha256(&mut self) -> Result<String, io::Error> {
+        let mut hasher = Sha256::new();
+        match self {
+            InputMode::Buffer(buf) => hasher.update(buf),
+            InputMode::File(file) => {
+                io::copy(file, &mut HashWriter(&mut hasher))?;
+                file.seek(SeekFrom::Start(0))?;
+            }
+            InputMode::Stream(reader, _) => {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+                hasher.update(&buf);
+                *self = InputMode::Buffer(buf);
+            }
+        }
+        Ok(hex(&hasher.finalize()))
+    }
+
     pub fn size(&self) -> u64 {
         match self {
             InputMode::Buffer(buf) => buf.len() as u64,
             InputMode::File(f) => f.metadata().expect("file has no size").len(),
+            InputMode::Stream(_, size) => *size,
+        }
+    }
+
+    /// Up to len bytes from the start of the data, leaving a file positioned
+    /// at its start.  A stream can't be peeked at, so it gives nothing.
+    pub fn head(&mut self, len: usize) -> Result<Vec<u8>, io::Error> {
+        match self {
+            InputMode::Buffer(buf) => Ok(buf[..buf.len().min(len)].to_vec()),
+            InputMode::File(file) => {
+                let mut head = Vec::with_capacity(len);
+                (&mut *file).take(len as u64).read_to_end(&mut head)?;
+                file.seek(SeekFrom::Start(0))?;
+                Ok(head)
+            }
+            InputMode::Stream(..) => Ok(Vec::new()),
+        }
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Feeds io::copy into a hasher
+pub struct HashWriter<'a>(pub &'a mut Sha256);
+
+impl io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub enum PasswordSource {
+    Literal(String),
+    File(PathBuf),
+    Env(String),
+    Prompt,
+    Generate,
+}
+
+impl PasswordSource {
+    /// The first password setting from a config file section, if any
+    pub fn from_config(
+        pw: Option<String>,
+        pw_file: Option<PathBuf>,
+        pw_env: Option<String>,
+        pw_prompt: Option<bool>,
+    ) -> Option<Self> {
+        pw.map(PasswordSource::Literal)
+            .or(pw_file.map(PasswordSource::File))
+            .or(pw_env.map(PasswordSource::Env))
+            .or(match pw_prompt {
+                Some(true) => Some(PasswordSource::Prompt),
+                _ => None,
+            })
+    }
+
+    pub fn resolve(self) -> Result<String, io::Error> {
+        match self {
+            PasswordSource::Literal(pw) => Ok(pw),
+            PasswordSource::File(path) => {
+                let pw = fs::read_to_string(path)?;
+                Ok(pw.lines().next().unwrap_or("").into())
+            }
+            PasswordSource::Env(name) => std::env::var(&name).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("environment variable {} is not set", name),
+                )
+            }),
+            PasswordSource::Prompt => rpassword::prompt_password("Enter the file password: "),
+            PasswordSource::Generate => generate_password(),
+        }
+    }
+}
+
+/// Every character the service allows in a file password
+const PASSWORD_CHARSET: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789._-";
+pub const GENERATED_PASSWORD_LEN: usize = 20;
+
+/// A password of the maximum allowed length, drawn uniformly from the full charset
+pub fn generate_password() -> Result<String, io::Error> {
+    // rejection sampling, so every character is equally likely
+    let limit = 256 - 256 % PASSWORD_CHARSET.len();
+    let mut pw = String::with_capacity(GENERATED_PASSWORD_LEN);
+    let mut buf = [0u8; 64];
+    while pw.len() < GENERATED_PASSWORD_LEN {
+        getrandom::getrandom(&mut buf)?;
+        for byte in buf.iter().map(|b| *b as usize).filter(|b| *b < limit) {
+            if pw.len() < GENERATED_PASSWORD_LEN {
+                pw.push(PASSWORD_CHARSET[byte % PASSWORD_CHARSET.len()] as char);
+            }
+        }
+    }
+    Ok(pw)
+}
+
+/// Split an ID:PASSWORD token printed by `push --gen-pw --pw-token`.
+/// Ids can contain ':' through their prefix, so only a trailing segment that
+/// looks like a generated password is treated as one.
+pub fn split_pw_token(token: &str) -> (String, Option<String>) {
+    if let Some((id, pw)) = token.rsplit_once(':') {
+        let generated =
+            pw.len() == GENERATED_PASSWORD_LEN && pw.bytes().all(|b| PASSWORD_CHARSET.contains(&b));
+        if generated && !id.is_empty() {
+            return (id.into(), Some(pw.into()));
+        }
+    }
+    (token.into(), None)
+}
+
+/// Whether `pull --wait` should treat an id as a prefix, and wait for the
+/// newest file pushed with it.  Prefixes conventionally end with ':', which
+/// the random part of an id never contains.
+pub fn is_wait_prefix(id: &str) -> bool {
+    id.ends_with(':') || is_name_prefix(id)
+}
+
+/// Separates a name from the random part of the id, see [Name]
+const NAME_SEPARATOR: char = '|';
+
+/// Whether an id is the prefix of a named file, which resolves to the
+/// newest file pushed with that name
+pub fn is_name_prefix(id: &str) -> bool {
+    id.ends_with(NAME_SEPARATOR)
+}
+
+/// A stable name for the newest file pushed with it.  Names are kept in the
+/// id as the prefix NAME|, so they work from any machine using the account.
+pub struct Name(pub String);
+
+impl FromStr for Name {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        // leaves room for the separator within the 64 character prefix limit
+        const NAME_PATTERN: &str = r"^[a-zA-Z0-9._\-]{1,63}$";
+        lazy_static! {
+            static ref NAME_RE: Regex = Regex::new(NAME_PATTERN).unwrap();
+        }
+        let text = name.trim();
+        if NAME_RE.is_match(text) {
+            Ok(Self(text.into()))
+        } else {
+            Err(Error::MalformedArgument(
+                "name",
+                name.into(),
+                NAME_PATTERN.into(),
+            ))
+        }
+    }
+}
+
+impl Name {
+    pub fn prefix(&self) -> String {
+        format!("{}{}", self.0, NAME_SEPARATOR)
+    }
+}
+
+/// A MIME type the raw url serves a file with, eg. image/png
+#[derive(Clone)]
+pub struct ContentType(pub String);
+
+impl FromStr for ContentType {
+    type Err = Error;
+
+    fn from_str(content_type: &str) -> Result<Self, Self::Err> {
+        const CONTENT_TYPE_PATTERN: &str =
+            r"^[a-zA-Z0-9!#$&^_.+\-]+/[a-zA-Z0-9!#$&^_.+\-]+(\s*;\s*[ -~]*)?$";
+        lazy_static! {
+            static ref CONTENT_TYPE_RE: Regex = Regex::new(CONTENT_TYPE_PATTERN).unwrap();
+        }
+        let text = content_type.trim();
+        if CONTENT_TYPE_RE.is_match(text) {
+            Ok(Self(text.into()))
+        } else {
+            Err(Error::MalformedArgument(
+                "content type",
+                content_type.into(),
+                CONTENT_TYPE_PATTERN.into(),
+            ))
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Prefix(pub String);
 
 impl FromStr for Prefix {
     type Err = Error;
 
     fn from_str(prefix: &str) -> Result<Self, Self::Err> {
-        const PREFIX_PATTERN: &str = r"^[a-zA-Z0-9._\-:|]{1,64}$";
+        // a trailing separator would make the prefix look like a name
+        const PREFIX_PATTERN: &str = r"^[a-zA-Z0-9._\-:|]{0,63}[a-zA-Z0-9._\-:]$";
         lazy_static! {
             static ref PREFIX_RE: Regex = Regex::new(PREFIX_PATTERN).unwrap();
         }
@@ -76,6 +328,7 @@ impl FromStr for Prefix {
     }
 }
 
+#[derive(Clone)]
 pub struct Lifetime(pub String);
 
 impl FromStr for Lifetime {
@@ -87,27 +340,99 @@ impl FromStr for Lifetime {
             static ref LIFETIME_RE: Regex = Regex::new(LIFETIME_PATTERN).unwrap();
         }
         let text = lifetime.trim();
-        if LIFETIME_RE.is_match(text) {
+        // the count must also fit in a u64, for Lifetime::duration
+        if LIFETIME_RE.is_match(text) && parse_duration(text).is_some() {
             Ok(Self(text.into()))
         } else {
             Err(Error::MalformedArgument(
                 "lifetime",
                 lifetime.into(),
-                LIFETIME_PATTERN.into(),
+                format!("{} with a count below 2^64", LIFETIME_PATTERN),
             ))
         }
     }
 }
 
-pub enum ResponseFormat {
-    TextJavascript,
-    TextPlain,
+/// How long `pull --wait` keeps polling for a file
+pub enum Wait {
+    Forever,
+    Timeout(Duration),
 }
 
-impl Default for ResponseFormat {
-    fn default() -> Self {
-        Self::TextPlain
+impl FromStr for Wait {
+    type Err = Error;
+
+    fn from_str(timeout: &str) -> Result<Self, Self::Err> {
+        match parse_duration(timeout) {
+            Some(duration) => Ok(Wait::Timeout(duration)),
+            None => Err(Error::MalformedArgument(
+                "timeout",
+                timeout.into(),
+                DURATION_PATTERN.into(),
+            )),
+        }
+    }
+}
+
+const DURATION_PATTERN: &str = r"^(\d+)(h|m|s)$";
+
+/// Parse a duration in the same format as a file lifetime, eg. 10m
+fn parse_duration(text: &str) -> Option<Duration> {
+    lazy_static! {
+        static ref DURATION_RE: Regex = Regex::new(DURATION_PATTERN).unwrap();
     }
+    let captures = DURATION_RE.captures(text.trim())?;
+    let count: u64 = captures[1].parse().ok()?;
+    let unit = match &captures[2] {
+        "h" => 3600,
+        "m" => 60,
+        _ => 1,
+    };
+    Some(Duration::from_secs(count.saturating_mul(unit)))
+}
+
+/// A number of bytes, eg. 512k or 10m, in powers of 1024
+#[derive(Clone, Copy)]
+pub struct Size(pub u64);
+
+impl FromStr for Size {
+    type Err = Error;
+
+    fn from_str(size: &str) -> Result<Self, Self::Err> {
+        const SIZE_PATTERN: &str = r"^(\d+)(k|m|g)?$";
+        lazy_static! {
+            static ref SIZE_RE: Regex = Regex::new(SIZE_PATTERN).unwrap();
+        }
+        let malformed = || Error::MalformedArgument("size", size.into(), SIZE_PATTERN.into());
+        let out_of_range = |reason: &str| Error::OutOfRange("size", size.into(), reason.into());
+        let too_big = || out_of_range("must be less than 2^64 bytes");
+        let captures = SIZE_RE.captures(size.trim()).ok_or_else(malformed)?;
+        let count: u64 = captures[1].parse().map_err(|_| too_big())?;
+        let unit = match captures.get(2).map(|unit| unit.as_str()) {
+            Some("g") => 1 << 30,
+            Some("m") => 1 << 20,
+            Some("k") => 1 << 10,
+            _ => 1,
+        };
+        match count.checked_mul(unit) {
+            Some(0) => Err(out_of_range("must be greater than 0")),
+            None => Err(too_big()),
+            Some(bytes) => Ok(Size(bytes)),
+        }
+    }
+}
+
+impl Lifetime {
+    pub fn duration(&self) -> Duration {
+        parse_duration(&self.0).expect("lifetimes are validated when parsed")
+    }
+}
+
+#[derive(Default)]
+pub enum ResponseFormat {
+    TextJavascript,
+    #[default]
+    TextPlain,
 }
 
 impl FromStr for ResponseFormat {
@@ -139,3 +464,188 @@ impl ResponseFormat {
         }
     }
 }
+
+/// Bytes start through end of a file, inclusive, as in an HTTP Range
+/// header.  Without an end the range runs to the end of the file.
+#[derive(Clone, Copy)]
+pub struct Range {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl Range {
+    /// The first count bytes
+    pub fn head(count: Size) -> Self {
+        Self {
+            start: 0,
+            end: Some(count.0 - 1),
+        }
+    }
+
+    /// Everything from start on
+    pub fn from(start: u64) -> Self {
+        Self { start, end: None }
+    }
+
+    pub fn header(&self) -> String {
+        match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end),
+            None => format!("bytes={}-", self.start),
+        }
+    }
+
+    /// How many bytes the range holds, if it has an end.  Parsing rejects
+    /// ranges with an end whose count doesn't fit.
+    pub fn count(&self) -> Option<u64> {
+        self.end
+            .and_then(|end| end.checked_sub(self.start)?.checked_add(1))
+    }
+}
+
+impl FromStr for Range {
+    type Err = Error;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        const RANGE_PATTERN: &str = r"^(\d+)-(\d*)$";
+        lazy_static! {
+            static ref RANGE_RE: Regex = Regex::new(RANGE_PATTERN).unwrap();
+        }
+        let malformed = || Error::MalformedArgument("range", range.into(), RANGE_PATTERN.into());
+        let out_of_range = |reason: &str| Error::OutOfRange("range", range.into(), reason.into());
+        let too_big = || out_of_range("must stay below 2^64 bytes");
+        let captures = RANGE_RE.captures(range.trim()).ok_or_else(malformed)?;
+        let start: u64 = captures[1].parse().map_err(|_| too_big())?;
+        let end: Option<u64> = match &captures[2] {
+            "" => None,
+            end => Some(end.parse().map_err(|_| too_big())?),
+        };
+        let parsed = Range { start, end };
+        match (end, parsed.count()) {
+            (Some(end), _) if end < start => Err(out_of_range("start must not be after end")),
+            (Some(_), None) => Err(too_big()),
+            _ => Ok(parsed),
+        }
+    }
+}
+
+/// When to report progress of uploads and downloads on stderr
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Progress {
+    /// A progress bar when stderr is a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+    /// One JSON object per line, for wrapper scripts
+    Json,
+}
+
+impl FromStr for Progress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Progress::Auto),
+            "always" => Ok(Progress::Always),
+            "never" => Ok(Progress::Never),
+            "json" => Ok(Progress::Json),
+            _ => Err(Error::MalformedArgument(
+                "progress",
+                s.into(),
+                "one of auto, always, never, json".into(),
+            )),
+        }
+    }
+}
+
+/// Quote text as a JSON string
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_parse_with_counts_that_fit() {
+        let count = |range: &str| range.parse::<Range>().unwrap().count();
+        assert_eq!(count("0-0"), Some(1));
+        assert_eq!(count("10-19"), Some(10));
+        assert_eq!(count("10-"), None);
+        assert_eq!(count("1-18446744073709551615"), Some(u64::MAX));
+        assert_eq!(count("18446744073709551615-18446744073709551615"), Some(1));
+        for range in [
+            "0-18446744073709551615",
+            "18446744073709551616-",
+            "5-4",
+            "-4",
+            "",
+            "1-2-3",
+        ] {
+            assert!(range.parse::<Range>().is_err(), "{}", range);
+        }
+        let err = |range: &str| range.parse::<Range>().err().unwrap().to_string();
+        assert_eq!(err("5-2"), "range was 5-2 but start must not be after end");
+        assert_eq!(
+            err("0-18446744073709551615"),
+            "range was 0-18446744073709551615 but must stay below 2^64 bytes"
+        );
+        assert!(err("-4").contains("must match"));
+    }
+
+    #[test]
+    fn heads_count_from_the_start() {
+        let err = |size: &str| size.parse::<Size>().err().unwrap().to_string();
+        assert_eq!(err("0"), "size was 0 but must be greater than 0");
+        assert_eq!(err("0k"), "size was 0k but must be greater than 0");
+        assert_eq!(
+            err("17179869184g"),
+            "size was 17179869184g but must be less than 2^64 bytes"
+        );
+        assert_eq!(
+            err("18446744073709551616"),
+            "size was 18446744073709551616 but must be less than 2^64 bytes"
+        );
+        assert!(err("1t").contains("must match"));
+        let head = Range::head("512".parse().unwrap());
+        assert_eq!((head.start, head.count()), (0, Some(512)));
+        assert_eq!(head.header(), "bytes=0-511");
+    }
+
+    #[test]
+    fn lifetimes_too_long_to_represent_are_rejected() {
+        for lifetime in [
+            "10m",
+            "1h",
+            "18446744073709551615s",
+            "18446744073709551615h",
+        ] {
+            let parsed: Lifetime = lifetime.parse().unwrap();
+            parsed.duration();
+        }
+        for lifetime in [
+            "99999999999999999999s",
+            "18446744073709551616m",
+            "",
+            "10",
+            "-1s",
+        ] {
+            assert!(lifetime.parse::<Lifetime>().is_err(), "{}", lifetime);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use super::api::{self, ClientOpts, DeleteArgs, ListArgs, PullArgs};
+
+/// How long to wait between listing files
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How many times a file is pulled before the watcher gives up on it
+const MAX_ATTEMPTS: u32 = 8;
+
+pub struct WatchArgs {
+    pub api_key: String,
+    pub endpoint: String,
+    pub prefix: Option<String>,
+    pub dir: Option<PathBuf>,
+    pub delete: bool,
+    /// Pull the files that were there at startup too, not only new ones
+    pub existing: bool,
+}
+
+/// The name a file is written to in a directory, from its id.  Ids can
+/// contain ':' and '|' through their prefix, which windows doesn't allow in
+/// file names, and an id from the server could hold a path separator or be
+/// a dot segment, which would point outside the directory.  A leading '.'
+/// is replaced too, so files never end up hidden.
+pub fn file_name(id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| match c {
+            ':' | '|' | '/' | '\\' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match name.strip_prefix('.') {
+        Some(rest) => format!("_{}", rest),
+        None if name.is_empty() => "_".into(),
+        None => name,
+    }
+}
+
+/// Pull one file into dir, through a temporary file so readers of the
+/// directory never see a partial file
+fn pull_to_dir(
+    args: &WatchArgs,
+    opts: ClientOpts,
+    dir: &Path,
+    id: &str,
+) -> Result<PathBuf, api::ErrorKind> {
+    let path = dir.join(file_name(id));
+    let partial = dir.join(format!(".{}.part", file_name(id)));
+    let file = fs::File::create(&partial).map_err(api::ErrorKind::LocalIoError)?;
+    let pull = PullArgs::new(
+        args.endpoint.clone(),
+        Some(id.into()),
+        Some(args.api_key.clone()),
+        None,
+        None,
+        file,
+    );
+    if let Err(err) = api::pull(pull, opts) {
+        let _ = fs::remove_file(&partial);
+        return Err(err);
+    }
+    fs::rename(&partial, &path).map_err(api::ErrorKind::LocalIoError)?;
+    Ok(path)
+}
+
+/// Pull one file into memory and only then write it out, so a pull that
+/// fails partway doesn't leave half a file on stdout ahead of its retry
+fn pull_to_stdout(args: &WatchArgs, opts: ClientOpts, id: &str) -> Result<(), api::ErrorKind> {
+    let mut buf = Vec::new();
+    let pull = PullArgs::new(
+        args.endpoint.clone(),
+        Some(id.into()),
+        Some(args.api_key.clone()),
+        None,
+        None,
+        &mut buf,
+    );
+    api::pull(pull, opts)?;
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&buf)
+        .and_then(|_| stdout.flush())
+        .map_err(api::ErrorKind::LocalIoError)
+}
+
+/// Failed pulls of one file, and the poll it's next tried on
+struct Failures {
+    attempts: u32,
+    retry_at: u64,
+}
+
+/// Pull every file matching the prefix as it shows up, until interrupted.
+/// Failures are reported and retried with a growing delay, so one bad file or
+/// a dropped connection doesn't stop the watcher; a file that keeps failing
+/// is given up on after MAX_ATTEMPTS.  Files with a password are skipped
+/// right away, since no password is passed, and so are the files that were
+/// there at startup, unless existing is set.
+pub fn run(args: WatchArgs, opts: ClientOpts) -> Result<(), api::ErrorKind> {
+    if let Some(dir) = &args.dir {
+        fs::create_dir_all(dir).map_err(api::ErrorKind::LocalIoError)?;
+    }
+    let prefix = args.prefix.as_deref().unwrap_or("");
+    let mut seen = HashSet::new();
+    if !args.existing {
+        let list = ListArgs::new(args.api_key.clone(), args.endpoint.clone());
+        seen.extend(api::list_ids(list)?);
+    }
+    let mut failures: HashMap<String, Failures> = HashMap::new();
+    let mut poll: u64 = 0;
+    loop {
+        let list = ListArgs::new(args.api_key.clone(), args.endpoint.clone());
+        let ids = match api::list_ids(list) {
+            Ok(ids) => ids,
+            Err(err) => {
+                eprintln!("unable to list files: {}", err);
+                Vec::new()
+            }
+        };
+        for id in ids {
+            if !id.starts_with(prefix) || seen.contains(&id) {
+                continue;
+            }
+            if failures
+                .get(&id)
+                .is_some_and(|failed| poll < failed.retry_at)
+            {
+                continue;
+            }
+            let pulled = match &args.dir {
+                Some(dir) => pull_to_dir(&args, opts, dir, &id).map(|path| {
+                    println!("{}", path.display());
+                    let _ = io::stdout().flush();
+                }),
+                None => pull_to_stdout(&args, opts, &id),
+            };
+            if let Err(api::ErrorKind::Denied(err)) = &pulled {
+                // watch has no password to pass, so retrying won't help
+                eprintln!(
+                    "skipped {}: it has a password, or can't be pulled with your api key ({})",
+                    id,
+                    err.trim()
+                );
+                failures.remove(&id);
+                seen.insert(id);
+                continue;
+            }
+            if let Err(err) = pulled {
+                let failed = failures.entry(id.clone()).or_insert(Failures {
+                    attempts: 0,
+                    retry_at: 0,
+                });
+                failed.attempts += 1;
+                if failed.attempts >= MAX_ATTEMPTS {
+                    eprintln!(
+                        "unable to pull {}: {}, giving up after {} attempts",
+                        id, err, failed.attempts
+                    );
+                    failures.remove(&id);
+                    seen.insert(id);
+                } else {
+                    // skip 1, 2, 4, ... polls before trying again
+                    failed.retry_at = poll + (1 << failed.attempts);
+                    if failed.attempts == 1 {
+                        eprintln!("unable to pull {}: {}, retrying", id, err);
+                    }
+                }
+                continue;
+            }
+            failures.remove(&id);
+            if args.delete {
+                let delete =
+                    DeleteArgs::new(args.api_key.clone(), args.endpoint.clone(), id.clone());
+                if let Err(err) = api::delete(delete, opts) {
+                    eprintln!("unable to delete {}: {}", id, err);
+                }
+            }
+            seen.insert(id);
+        }
+        thread::sleep(POLL_INTERVAL);
+        poll += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_stay_inside_the_directory() {
+        assert_eq!(file_name("logs:|0000003d"), "logs__0000003d");
+        for (id, name) in [
+            ("..", "_."),
+            (".", "_"),
+            ("../etc/passwd", "_._etc_passwd"),
+            ("a/../../b", "a_.._.._b"),
+            ("..\\windows", "_._windows"),
+            ("/abs", "_abs"),
+            (".bashrc", "_bashrc"),
+            ("", "_"),
+            ("tab\tid", "tab_id"),
+        ] {
+            assert_eq!(file_name(id), name, "{}", id);
+            assert_eq!(Path::new(name).components().count(), 1, "{}", name);
+        }
+    }
+}